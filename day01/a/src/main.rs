@@ -1,5 +1,7 @@
+use a::digits::decimal_value;
 use anyhow::{Context, Result};
-use clap::{arg, Parser};
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
 use std::env::current_dir;
 use std::io::BufRead;
 
@@ -9,48 +11,120 @@ use std::io::BufRead;
 struct Args {
     #[arg(short, long, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    /// Print a per-line calibration report instead of just the sum
+    #[arg(short, long, value_enum)]
+    report: Option<ReportFormat>,
 }
 
-fn read_buffer(reader: Box<dyn std::io::BufRead>) -> Result<u128> {
-    reader.lines().into_iter().fold(Ok(0), |sum_so_far, line| {
-        let line = line?;
-        let line = line.trim_end();
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+/// Part a only recognises numerals, so unlike part b there is no recognition field
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+struct Digit {
+    value: u32,
+    /// Offset of the digit in characters (not bytes) from the start of the line
+    offset: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct Calibration {
+    /// 1-based line number in the input
+    line: usize,
+    first: Option<Digit>,
+    last: Option<Digit>,
+}
+
+impl Calibration {
+    fn value(&self) -> Result<u128> {
+        let first = self
+            .first
+            .with_context(|| format!("Line {} has no digit", self.line))?;
+        let last = self
+            .last
+            .with_context(|| format!("Line {} has no digit", self.line))?;
+        Ok(first.value as u128 * 10 + last.value as u128)
+    }
+}
 
-        if line.len() < 1 {
-            sum_so_far
-        } else {
-            let pair = line.chars().fold((None, None), |acc, c| {
-                if let Some(digit) = c.to_digit(10) {
-                    if acc.0.is_none() {
-                        (Some(digit as u128), Some(digit as u128))
-                    } else {
-                        (acc.0, Some(digit as u128))
-                    }
-                } else {
-                    acc
+fn calibrate_line(number: usize, line: &str) -> Calibration {
+    line.chars().enumerate().fold(
+        Calibration {
+            line: number,
+            first: None,
+            last: None,
+        },
+        |acc, (offset, c)| match decimal_value(c) {
+            Some(value) => {
+                let digit = Some(Digit { value, offset });
+                Calibration {
+                    first: acc.first.or(digit),
+                    last: digit,
+                    ..acc
                 }
-            });
+            }
+            None => acc,
+        },
+    )
+}
 
-            let sum = sum_so_far?;
-            let first = (pair.0).context("First digit")?;
-            let second = (pair.1).context("Second digit")?;
-            let pair = first * 10 + second;
+fn parse_buffer(reader: Box<dyn std::io::BufRead>) -> Result<Vec<Calibration>> {
+    let mut calibrations = vec![];
 
-            Ok(sum + pair)
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim_end();
+        if !line.is_empty() {
+            calibrations.push(calibrate_line(idx + 1, line));
         }
-    })
+    }
+
+    Ok(calibrations)
 }
 
+fn sum_calibrations(calibrations: &[Calibration]) -> Result<u128> {
+    calibrations.iter().map(Calibration::value).sum()
+}
+
+#[cfg(test)]
 fn read_str(text: String) -> Result<u128> {
     let cursor = std::io::Cursor::new(text);
     let reader = std::io::BufReader::new(cursor);
-    read_buffer(Box::new(reader))
+    sum_calibrations(&parse_buffer(Box::new(reader))?)
 }
 
-fn read_path(path: &std::path::Path) -> Result<u128> {
+fn parse_path(path: &std::path::Path) -> Result<Vec<Calibration>> {
     let file = std::fs::File::open(path)?;
     let reader = std::io::BufReader::new(file);
-    read_buffer(Box::new(reader))
+    parse_buffer(Box::new(reader))
+}
+
+fn print_table(calibrations: &[Calibration]) {
+    let describe = |digit: &Option<Digit>| match digit {
+        Some(digit) => format!("{} @{}", digit.value, digit.offset),
+        None => "-".to_string(),
+    };
+
+    println!(
+        "{:>6}  {:<10}  {:<10}  {:>5}",
+        "line", "first", "last", "value"
+    );
+    for calibration in calibrations {
+        let value = calibration
+            .value()
+            .map_or_else(|_| "-".to_string(), |value| value.to_string());
+        println!(
+            "{:>6}  {:<10}  {:<10}  {:>5}",
+            calibration.line,
+            describe(&calibration.first),
+            describe(&calibration.last),
+            value
+        );
+    }
 }
 
 #[test]
@@ -68,6 +142,46 @@ treb7uchet"
     Ok(())
 }
 
+#[test]
+fn test_unicode_digits() -> Result<()> {
+    // VV: Full-width 7 and 2, Arabic-Indic 3 and Devanagari 5
+    let number = read_str("ａ７b２\n٣x\n1५".to_string());
+    assert_eq!(number?, 72 + 33 + 15);
+
+    let calibration = calibrate_line(1, "é٣x");
+    assert_eq!(
+        calibration.first,
+        Some(Digit {
+            value: 3,
+            offset: 1
+        })
+    );
+
+    let error = read_str("1abc2\nnothing here\n".to_string()).unwrap_err();
+    assert_eq!(error.to_string(), "Line 2 has no digit");
+
+    Ok(())
+}
+
+#[test]
+fn test_supplementary_digits() {
+    use a::digits::decimal_value;
+
+    // VV: Chakma, Adlam, segmented and Myanmar Extended-C digits
+    for (c, value) in [
+        ('\u{11136}', 0),
+        ('\u{1E959}', 9),
+        ('\u{1FBF4}', 4),
+        ('\u{116DB}', 1),
+        ('\u{1E5FA}', 9),
+    ] {
+        assert_eq!(decimal_value(c), Some(value));
+    }
+    // VV: Kaktovik numerals are not decimal digits
+    assert_eq!(decimal_value('\u{1D2C5}'), None);
+    assert_eq!(decimal_value('x'), None);
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
     let cwd = current_dir()?;
@@ -78,8 +192,18 @@ fn main() -> Result<()> {
         cwd.join(args.input)
     };
 
-    let result = read_path(&path_input)?;
-    println!("{result}");
+    let calibrations = parse_path(&path_input)?;
+
+    match args.report {
+        Some(ReportFormat::Table) => print_table(&calibrations),
+        Some(ReportFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&calibrations)?)
+        }
+        None => {
+            let result = sum_calibrations(&calibrations)?;
+            println!("{result}");
+        }
+    }
 
     Ok(())
 }
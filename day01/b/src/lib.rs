@@ -0,0 +1,2 @@
+#[path = "../../a/src/digits.rs"]
+pub mod digits;
@@ -1,5 +1,7 @@
 use anyhow::{Context, Result};
-use clap::{arg, Parser};
+use b::digits::decimal_value;
+use clap::{Parser, ValueEnum};
+use serde::Serialize;
 use std::env::current_dir;
 use std::io::BufRead;
 
@@ -9,70 +11,149 @@ use std::io::BufRead;
 struct Args {
     #[arg(short, long, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    /// Print a per-line calibration report instead of just the sum
+    #[arg(short, long, value_enum)]
+    report: Option<ReportFormat>,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ReportFormat {
+    Table,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Recognition {
+    Numeral,
+    Word,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+struct Digit {
+    value: u32,
+    /// Offset of the digit in characters (not bytes) from the start of the line
+    offset: usize,
+    recognition: Recognition,
+}
+
+#[derive(Debug, Serialize)]
+struct Calibration {
+    /// 1-based line number in the input
+    line: usize,
+    first: Option<Digit>,
+    last: Option<Digit>,
+}
+
+impl Calibration {
+    fn value(&self) -> Result<u128> {
+        let first = self
+            .first
+            .with_context(|| format!("Line {} has no digit", self.line))?;
+        let last = self
+            .last
+            .with_context(|| format!("Line {} has no digit", self.line))?;
+        Ok(first.value as u128 * 10 + last.value as u128)
+    }
 }
 
-fn read_buffer(reader: Box<dyn std::io::BufRead>) -> Result<u128> {
-    let digits = vec![
+fn calibrate_line(number: usize, line: &str) -> Calibration {
+    let words = [
         "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
     ];
 
-    reader.lines().into_iter().fold(Ok(0), |sum_so_far, line| {
+    let mut calibration = Calibration {
+        line: number,
+        first: None,
+        last: None,
+    };
+
+    for (offset, (start, c)) in line.char_indices().enumerate() {
+        let digit = if let Some(value) = decimal_value(c) {
+            Some(Digit {
+                value,
+                offset,
+                recognition: Recognition::Numeral,
+            })
+        } else {
+            // VV: Puzzle is fine with digits sharing letters
+            // e.g. eightwothree is equivalent to 823
+            words
+                .iter()
+                .position(|word| line[start..].starts_with(word))
+                .map(|i| Digit {
+                    value: i as u32 + 1,
+                    offset,
+                    recognition: Recognition::Word,
+                })
+        };
+
+        if digit.is_some() {
+            calibration.first = calibration.first.or(digit);
+            calibration.last = digit;
+        }
+    }
+
+    calibration
+}
+
+fn parse_buffer(reader: Box<dyn std::io::BufRead>) -> Result<Vec<Calibration>> {
+    let mut calibrations = vec![];
+
+    for (idx, line) in reader.lines().enumerate() {
         let line = line?;
         let line = line.trim_end();
-        if line.len() < 1 {
-            sum_so_far
-        } else {
-            let mut pair = (None, None);
-            let mut idx = 0;
-
-            while idx < line.len() {
-                let c = line.chars().nth(idx).context("Getting a character")?;
-                let mut digit = None;
-                idx += 1;
-
-                if let Some(d) = c.to_digit(10) {
-                    digit = Some(d);
-                } else {
-                    for (i, word) in digits.iter().enumerate() {
-                        if line[(idx-1)..].starts_with(*word) {
-                            digit = Some((i as u32 ) + 1);
-                            // VV: Puzzle is fine with digits sharing letters
-                            // e.g. eightwothree is equivalent to 823
-                            // idx += word.len() -1;
-                            break
-                        }
-                    }
-                }
-
-                if let Some(digit) = digit {
-                    if pair.0.is_none() {
-                        pair =  (Some(digit as u128), Some(digit as u128));
-                    } else {
-                        pair = (pair.0, Some(digit as u128));
-                    }
-                }
-            }
-
-            let sum = sum_so_far?;
-            let first = (pair.0).context("First digit")?;
-            let second = (pair.1).context("Second digit")?;
-            let pair = first * 10 + second;
-
-            Ok(sum + pair)
+        if !line.is_empty() {
+            calibrations.push(calibrate_line(idx + 1, line));
         }
-    })
+    }
+
+    Ok(calibrations)
 }
 
+fn sum_calibrations(calibrations: &[Calibration]) -> Result<u128> {
+    calibrations.iter().map(Calibration::value).sum()
+}
+
+#[cfg(test)]
 fn read_str(text: String) -> Result<u128> {
     let cursor = std::io::Cursor::new(text);
     let reader = std::io::BufReader::new(cursor);
-    read_buffer(Box::new(reader))
+    sum_calibrations(&parse_buffer(Box::new(reader))?)
 }
 
-fn read_path(path: &std::path::Path) -> Result<u128> {
+fn parse_path(path: &std::path::Path) -> Result<Vec<Calibration>> {
     let file = std::fs::File::open(path).with_context(|| "Could not find input file")?;
     let reader = std::io::BufReader::new(file);
-    read_buffer(Box::new(reader))
+    parse_buffer(Box::new(reader))
+}
+
+fn print_table(calibrations: &[Calibration]) {
+    let describe = |digit: &Option<Digit>| match digit {
+        Some(digit) => format!(
+            "{} @{} ({:?})",
+            digit.value, digit.offset, digit.recognition
+        ),
+        None => "-".to_string(),
+    };
+
+    println!(
+        "{:>6}  {:<20}  {:<20}  {:>5}",
+        "line", "first", "last", "value"
+    );
+    for calibration in calibrations {
+        let value = calibration
+            .value()
+            .map_or_else(|_| "-".to_string(), |value| value.to_string());
+        println!(
+            "{:>6}  {:<20}  {:<20}  {:>5}",
+            calibration.line,
+            describe(&calibration.first),
+            describe(&calibration.last),
+            value
+        );
+    }
 }
 
 #[test]
@@ -93,6 +174,39 @@ zoneight234
     Ok(())
 }
 
+#[test]
+fn test_unicode_digits() -> Result<()> {
+    // VV: Full-width 7, Arabic-Indic 3 and Devanagari 5
+    let number = read_str("ａ７b\n٣x\nnine५".to_string());
+    assert_eq!(number?, 77 + 33 + 95);
+
+    let calibration = calibrate_line(1, "é٣twone");
+    assert_eq!(
+        calibration.first,
+        Some(Digit {
+            value: 3,
+            offset: 1,
+            recognition: Recognition::Numeral
+        })
+    );
+    assert_eq!(
+        calibration.last,
+        Some(Digit {
+            value: 1,
+            offset: 4,
+            recognition: Recognition::Word
+        })
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_digit_names_line() {
+    let error = read_str("1abc2\n\nnothing here\n".to_string()).unwrap_err();
+    assert_eq!(error.to_string(), "Line 3 has no digit");
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
     let cwd = current_dir()?;
@@ -103,8 +217,18 @@ fn main() -> Result<()> {
         cwd.join(args.input)
     };
 
-    let result = read_path(&path_input)?;
-    println!("{result}");
+    let calibrations = parse_path(&path_input)?;
+
+    match args.report {
+        Some(ReportFormat::Table) => print_table(&calibrations),
+        Some(ReportFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&calibrations)?)
+        }
+        None => {
+            let result = sum_calibrations(&calibrations)?;
+            println!("{result}");
+        }
+    }
 
     Ok(())
 }
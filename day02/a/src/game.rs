@@ -0,0 +1,160 @@
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufRead;
+
+/// The number of cubes of each colour, colours which are missing have 0 cubes.
+/// Also used to describe the contents of a bag.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Round {
+    pub cubes: BTreeMap<String, u32>,
+}
+
+impl Round {
+    pub fn count(&self, colour: &str) -> u32 {
+        self.cubes.get(colour).copied().unwrap_or(0)
+    }
+
+    pub fn total(&self) -> u32 {
+        self.cubes.values().sum()
+    }
+
+    /// Whether the cubes of this round could have been drawn from @bag
+    pub fn fits_in(&self, bag: &Round) -> bool {
+        self.cubes
+            .iter()
+            .all(|(colour, number)| *number <= bag.count(colour))
+    }
+
+    /// The product of the cube counts over @colours, a colour the round does not draw counts as 0
+    pub fn power(&self, colours: &BTreeSet<String>) -> u128 {
+        colours
+            .iter()
+            .map(|colour| self.count(colour) as u128)
+            .product()
+    }
+}
+
+impl std::str::FromStr for Round {
+    type Err = anyhow::Error;
+
+    /// Parses "3 blue, 4 red" into a Round
+    fn from_str(text: &str) -> Result<Self> {
+        let mut round = Round::default();
+
+        for cubes in text.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+            let mut number_colour = cubes.split_whitespace();
+            let number = number_colour.next().with_context(|| "Number in round")?;
+            let colour = number_colour.next().with_context(|| "Colour in round")?;
+            let number = number
+                .parse::<u32>()
+                .with_context(|| format!("Invalid number of cubes {number}"))?;
+
+            if let Some(what) = number_colour.next() {
+                bail!("Unexpected {what} after {number} {colour}");
+            }
+
+            if round.cubes.insert(colour.to_string(), number).is_some() {
+                bail!("Colour {colour} appears twice in {text}");
+            }
+        }
+
+        Ok(round)
+    }
+}
+
+impl std::fmt::Display for Round {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cubes = self
+            .cubes
+            .iter()
+            .map(|(colour, number)| format!("{number} {colour}"))
+            .collect::<Vec<_>>();
+        write!(f, "{}", cubes.join(", "))
+    }
+}
+
+#[derive(Debug)]
+pub struct Game {
+    pub id: u32,
+    pub rounds: Vec<Round>,
+}
+
+impl Game {
+    /// The fewest cubes of each colour that a bag must hold for every round to be possible
+    pub fn min_bag(&self) -> Round {
+        self.rounds.iter().fold(Round::default(), |mut acc, round| {
+            for (colour, number) in &round.cubes {
+                let entry = acc.cubes.entry(colour.clone()).or_insert(0);
+                *entry = (*entry).max(*number);
+            }
+            acc
+        })
+    }
+}
+
+/// Every colour that any round of @games draws
+pub fn colours_of(games: &[Game]) -> BTreeSet<String> {
+    games
+        .iter()
+        .flat_map(|game| game.rounds.iter())
+        .flat_map(|round| round.cubes.keys().cloned())
+        .collect()
+}
+
+pub fn parse_reader(reader: Box<dyn std::io::BufRead>) -> Result<Vec<Game>> {
+    let mut ret = vec![];
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if !line.starts_with("Game ") {
+            bail!("Line {line} does not start with \"Game \"");
+        }
+        let line = &line[5..];
+        let mut parts = line.split(':');
+        let id = parts.next().with_context(|| "Part of line containing ID")?;
+        let id = id.parse::<u32>().with_context(|| "Parsing ID of game")?;
+        let remaining = parts
+            .next()
+            .with_context(|| "Part of line containing rounds")?
+            .trim();
+
+        let rounds = remaining
+            .split(';')
+            .map(|part| part.parse::<Round>())
+            .collect::<Result<Vec<_>>>()
+            .with_context(|| format!("Parsing rounds of game {id}"))?;
+
+        ret.push(Game { id, rounds })
+    }
+
+    Ok(ret)
+}
+
+pub fn parse_str(text: &str) -> Result<Vec<Game>> {
+    let cursor = std::io::Cursor::new(text.to_string());
+    let reader = std::io::BufReader::new(cursor);
+    parse_reader(Box::new(reader))
+}
+
+pub fn parse_path(path: &std::path::Path) -> Result<Vec<Game>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Could not open input file {0}", path.display()))?;
+    let reader = std::io::BufReader::new(file);
+    parse_reader(Box::new(reader))
+}
+
+/// Reads the contents of a bag from a file with one or more "<number> <colour>" entries per line
+pub fn parse_bag_path(path: &std::path::Path) -> Result<Round> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not open bag file {0}", path.display()))?;
+    text.lines()
+        .collect::<Vec<_>>()
+        .join(",")
+        .parse::<Round>()
+        .with_context(|| format!("Parsing bag file {0}", path.display()))
+}
@@ -1,103 +1,28 @@
-use anyhow::{bail, Context, Result};
-use clap::{arg, Parser};
-use std::io::BufRead;
+use a::game::{parse_bag_path, parse_path, Game, Round};
+use anyhow::{Context, Result};
+use clap::Parser;
 
 #[derive(Debug, Parser)]
 #[command(version, about)]
 struct Args {
     #[arg(short, long, default_value = "input/mine")]
     input: std::path::PathBuf,
-}
 
-struct Round {
-    blue: u32,
-    red: u32,
-    green: u32,
-}
+    /// Cubes in the bag, using the same syntax as a round e.g. "12 red, 13 green, 14 blue"
+    #[arg(short, long, conflicts_with = "bag_file")]
+    bag: Option<String>,
 
-struct Game {
-    id: u32,
-    rounds: Vec<Round>,
+    /// File with the cubes in the bag, one or more "<number> <colour>" entries per line
+    #[arg(long)]
+    bag_file: Option<std::path::PathBuf>,
 }
 
-fn parse_reader(mut reader: Box<dyn std::io::BufRead>) -> Result<Vec<Game>> {
-    let mut ret = vec![];
-    for line in reader.lines() {
-        let line = line?;
-        let line = line.trim();
-
-        if line.len() == 0 {
-            continue;
-        }
-
-        if !line.starts_with("Game ") {
-            bail!("Line {line} does not start with \"Game \"");
-        }
-        let line = &line[5..];
-        let mut parts = line.split(':');
-        let id = parts.next().with_context(|| "Part of line containing ID")?;
-        let id = id.parse::<u32>().with_context(|| "Parsing ID of game")?;
-        let mut rounds = vec![];
-        let remaining = parts
-            .next()
-            .with_context(|| "Part of line containing rounds")?
-            .trim();
-
-        for part in remaining.split("; ") {
-            let mut round = Round {
-                blue: 0,
-                red: 0,
-                green: 0,
-            };
-
-            for cubes in part.split(", ") {
-                let mut number_colour = cubes.split(" ");
-                let number = number_colour.next().with_context(|| "Number in round")?;
-                let colour = number_colour.next().with_context(|| "Colour in round")?;
-                let number = number
-                    .parse::<u32>()
-                    .with_context(|| "Invalid number of cubes")?;
-
-                match colour {
-                    "blue" => round.blue = number,
-                    "red" => round.red = number,
-                    "green" => round.green = number,
-                    what => bail!("Invalid colour {what}"),
-                }
-            }
-
-            rounds.push(round);
-        }
-
-        ret.push(Game { id, rounds })
-    }
-
-    Ok(ret)
-}
-
-fn parse_str(text: &str) -> Result<Vec<Game>> {
-    let cursor = std::io::Cursor::new(text.to_string());
-    let reader = std::io::BufReader::new(cursor);
-    parse_reader(Box::new(reader))
-}
-
-fn parse_path(path: &std::path::Path) -> Result<Vec<Game>> {
-    let file = std::fs::File::open(path)
-        .with_context(|| format!("Could not open input file {0}", path.display()))?;
-    let reader = std::io::BufReader::new(file);
-    parse_reader(Box::new(reader))
-}
-
-fn find_possible_games(games: &Vec<Game>, context: &Round) -> u32 {
-    games.iter().fold(0, |acc, game| {
-        if game.rounds.iter().all(|round| {
-            round.red <= context.red && round.green <= context.green && round.blue <= context.blue
-        }) {
-            acc + game.id
-        } else {
-            acc
-        }
-    })
+fn find_possible_games(games: &[Game], context: &Round) -> u32 {
+    games
+        .iter()
+        .filter(|game| game.rounds.iter().all(|round| round.fits_in(context)))
+        .map(|game| game.id)
+        .sum()
 }
 
 #[test]
@@ -108,29 +33,51 @@ Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
 Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
 Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
-    let games = parse_str(sample)?;
+    let games = a::game::parse_str(sample)?;
 
-    let solution = find_possible_games(
-        &games,
-        &Round {
-            red: 12,
-            green: 13,
-            blue: 14,
-        },
-    );
+    let solution = find_possible_games(&games, &"12 red, 13 green, 14 blue".parse()?);
 
     assert_eq!(solution, 8);
     Ok(())
 }
 
+#[test]
+fn test_any_colour() -> Result<()> {
+    let sample = "Game 1: 3 cyan, 4 red; 1 magenta
+Game 2: 1 cyan; 5 magenta
+Game 3: 2 yellow";
+
+    let games = a::game::parse_str(sample)?;
+
+    // VV: Colours which are not in the bag cannot be drawn at all
+    let solution = find_possible_games(&games, &"4 red, 3 cyan, 2 magenta".parse()?);
+
+    assert_eq!(solution, 1);
+
+    // VV: A repeated colour would silently lose one of its counts
+    assert!("1 red, 2 red".parse::<Round>().is_err());
+    assert!(a::game::parse_str("Game 1: 1 red; 2 blue, 3 blue").is_err());
+    assert!(a::game::parse_str("Game 1: 1 red; 2 red").is_ok());
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
     let cwd = std::env::current_dir()?;
     let path = cwd.join(&args.input);
 
+    let bag = if let Some(bag) = &args.bag {
+        bag.parse::<Round>().with_context(|| "Parsing --bag")?
+    } else if let Some(bag_file) = &args.bag_file {
+        parse_bag_path(&cwd.join(bag_file))?
+    } else {
+        "12 red, 13 green, 14 blue".parse::<Round>()?
+    };
+
     let games = parse_path(&path)?;
-    let solution = find_possible_games(&games, & Round{red: 12, green: 13, blue:14});
+    let solution = find_possible_games(&games, &bag);
 
     println!("{solution}");
 
@@ -0,0 +1,195 @@
+use crate::game::{colours_of, Game, Round};
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ColourStats {
+    pub max: u32,
+    /// Mean over all rounds, rounds which do not draw the colour count as 0. 0 without rounds
+    pub mean: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameStats {
+    pub id: u32,
+    pub rounds: usize,
+    /// The power of the minimum bag of the game over every colour of the input
+    pub power: u128,
+    pub colours: BTreeMap<String, ColourStats>,
+    /// Whether the game is possible with each of the bags, in the order they were given
+    pub feasible: Vec<bool>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ColourSummary {
+    pub colour: String,
+    /// Number of games with at least one round that draws the colour
+    pub games: usize,
+    pub max: u32,
+    /// Mean over all rounds of all games, rounds which do not draw the colour count as 0. 0
+    /// without rounds
+    pub mean: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Analysis {
+    pub bags: Vec<String>,
+    pub games: Vec<GameStats>,
+    pub colours: Vec<ColourSummary>,
+}
+
+/// The mean of @count values which add up to @sum, 0 rather than NaN when there are none
+fn mean(sum: u32, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        sum as f64 / count as f64
+    }
+}
+
+/// Statistics of @game, its power is over @all_colours
+pub fn game_stats(game: &Game, all_colours: &BTreeSet<String>, bags: &[Round]) -> GameStats {
+    let min_bag = game.min_bag();
+    let rounds = game.rounds.len();
+
+    let colours = min_bag
+        .cubes
+        .iter()
+        .map(|(colour, max)| {
+            let sum = game
+                .rounds
+                .iter()
+                .map(|round| round.count(colour))
+                .sum::<u32>();
+            let stats = ColourStats {
+                max: *max,
+                mean: mean(sum, rounds),
+            };
+            (colour.clone(), stats)
+        })
+        .collect();
+
+    GameStats {
+        id: game.id,
+        rounds,
+        power: min_bag.power(all_colours),
+        colours,
+        feasible: bags.iter().map(|bag| min_bag.fits_in(bag)).collect(),
+    }
+}
+
+pub fn colour_summaries(games: &[Game]) -> Vec<ColourSummary> {
+    let rounds = games.iter().map(|game| game.rounds.len()).sum::<usize>();
+
+    colours_of(games)
+        .into_iter()
+        .map(|colour| {
+            let counts = games
+                .iter()
+                .flat_map(|game| game.rounds.iter())
+                .map(|round| round.count(&colour))
+                .collect::<Vec<_>>();
+
+            ColourSummary {
+                games: games
+                    .iter()
+                    .filter(|game| game.min_bag().count(&colour) > 0)
+                    .count(),
+                max: counts.iter().copied().max().unwrap_or(0),
+                mean: mean(counts.iter().sum(), rounds),
+                colour,
+            }
+        })
+        .collect()
+}
+
+pub fn analyse(games: &[Game], bags: &[Round]) -> Analysis {
+    let colours = colours_of(games);
+
+    Analysis {
+        bags: bags.iter().map(|bag| bag.to_string()).collect(),
+        games: games
+            .iter()
+            .map(|game| game_stats(game, &colours, bags))
+            .collect(),
+        colours: colour_summaries(games),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn write_csv_row(out: &mut impl Write, fields: &[String]) -> Result<()> {
+    let fields = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>();
+    writeln!(out, "{}", fields.join(","))?;
+    Ok(())
+}
+
+/// Writes one row per game, with a max and mean column for every colour that any game draws
+/// and a feasibility column for every bag
+pub fn write_games_csv(out: &mut impl Write, analysis: &Analysis) -> Result<()> {
+    let colours = analysis
+        .colours
+        .iter()
+        .map(|summary| &summary.colour)
+        .collect::<Vec<_>>();
+
+    let mut header = vec!["game".to_string(), "rounds".into(), "power".into()];
+    for colour in &colours {
+        header.push(format!("max {colour}"));
+        header.push(format!("mean {colour}"));
+    }
+    for bag in &analysis.bags {
+        header.push(format!("feasible with {bag}"));
+    }
+    write_csv_row(out, &header)?;
+
+    for game in &analysis.games {
+        let mut row = vec![
+            game.id.to_string(),
+            game.rounds.to_string(),
+            game.power.to_string(),
+        ];
+        for colour in &colours {
+            match game.colours.get(*colour) {
+                Some(stats) => {
+                    row.push(stats.max.to_string());
+                    row.push(stats.mean.to_string());
+                }
+                None => row.extend(["0".to_string(), "0".to_string()]),
+            }
+        }
+        row.extend(game.feasible.iter().map(|f| f.to_string()));
+        write_csv_row(out, &row)?;
+    }
+
+    Ok(())
+}
+
+pub fn write_colours_csv(out: &mut impl Write, analysis: &Analysis) -> Result<()> {
+    write_csv_row(
+        out,
+        &["colour".into(), "games".into(), "max".into(), "mean".into()],
+    )?;
+
+    for summary in &analysis.colours {
+        write_csv_row(
+            out,
+            &[
+                summary.colour.clone(),
+                summary.games.to_string(),
+                summary.max.to_string(),
+                summary.mean.to_string(),
+            ],
+        )?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,254 @@
+use crate::game::{Game, Round};
+use anyhow::{bail, Result};
+
+/// The prior belief about the total number of cubes in a bag.
+/// For a given size every split of the cubes between the colours is equally likely.
+#[derive(Debug, Clone, Copy)]
+pub enum Prior {
+    Uniform,
+    Poisson { mean: f64 },
+}
+
+impl Prior {
+    /// A poisson prior, @mean must be positive for its weights to be defined
+    pub fn poisson(mean: f64) -> Result<Self> {
+        if !(mean > 0.0 && mean.is_finite()) {
+            bail!("The mean of the poisson prior must be positive, not {mean}");
+        }
+        Ok(Prior::Poisson { mean })
+    }
+
+    fn ln_weight(&self, size: u32, ln_fact: &LnFactorial) -> f64 {
+        match self {
+            Prior::Uniform => 0.0,
+            Prior::Poisson { mean } => size as f64 * mean.ln() - mean - ln_fact.get(size),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    /// How many cubes beyond the minimum bag (see Game::min_bag()) to consider
+    pub max_extra: u32,
+    pub prior: Prior,
+}
+
+#[derive(Debug)]
+pub struct Inference {
+    pub id: u32,
+    /// The bag which maximises the likelihood of the game
+    pub best: Round,
+    /// The natural logarithm of the likelihood of the game given @best
+    pub ln_likelihood: f64,
+    /// Whether @best is one of the largest bags considered i.e. the likelihood was still rising
+    /// when the search stopped at Config::max_extra
+    pub at_limit: bool,
+    /// The natural logarithm of the likelihood that the game approaches as the bag grows without
+    /// bound while holding the colours in the proportions the game draws them
+    pub ln_unbounded: f64,
+    /// The posterior probability of each bag size in ascending size order
+    pub posterior: Vec<(u32, f64)>,
+}
+
+impl Inference {
+    /// How surprising the game is even under its most likely bag, in bits. Bags which grow
+    /// without bound are considered too so that this does not depend on Config::max_extra
+    pub fn surprise(&self) -> f64 {
+        -self.ln_likelihood.max(self.ln_unbounded) / std::f64::consts::LN_2
+    }
+
+    pub fn posterior_mode(&self) -> u32 {
+        self.posterior
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(0, |(size, _)| *size)
+    }
+
+    pub fn posterior_mean(&self) -> f64 {
+        self.posterior
+            .iter()
+            .map(|(size, p)| *size as f64 * p)
+            .sum()
+    }
+}
+
+/// Natural logarithms of n! for 0 <= n <= max
+struct LnFactorial(Vec<f64>);
+
+impl LnFactorial {
+    fn new(max: u32) -> Self {
+        let mut table = vec![0.0; max as usize + 1];
+        for n in 1..table.len() {
+            table[n] = table[n - 1] + (n as f64).ln();
+        }
+        Self(table)
+    }
+
+    fn get(&self, n: u32) -> f64 {
+        self.0[n as usize]
+    }
+
+    fn ln_choose(&self, n: u32, k: u32) -> f64 {
+        if k > n {
+            f64::NEG_INFINITY
+        } else {
+            self.get(n) - self.get(k) - self.get(n - k)
+        }
+    }
+}
+
+/// The natural logarithm of the probability of the rounds in @game if each round draws its
+/// cubes without replacement from @bag and then puts them back before the next round
+fn ln_likelihood(game: &Game, bag: &Round, ln_fact: &LnFactorial) -> f64 {
+    let total = bag.total();
+
+    game.rounds
+        .iter()
+        .map(|round| {
+            let ways = round
+                .cubes
+                .iter()
+                .map(|(colour, number)| ln_fact.ln_choose(bag.count(colour), *number))
+                .sum::<f64>();
+            ways - ln_fact.ln_choose(total, round.total())
+        })
+        .sum()
+}
+
+/// The natural logarithm of the probability of the rounds in @game if each round draws its
+/// cubes with replacement, with each colour as likely as its share of all the cubes the game
+/// draws. This is the limit of ln_likelihood() for ever larger bags with those proportions.
+/// Colours which the game never draws any cubes of cannot be drawn and so are left out.
+fn ln_unbounded(game: &Game, ln_fact: &LnFactorial) -> f64 {
+    let min_bag = game.min_bag();
+    let drawn = |colour: &str| {
+        game.rounds
+            .iter()
+            .map(|round| round.count(colour))
+            .sum::<u32>()
+    };
+    let total = game.rounds.iter().map(Round::total).sum::<u32>();
+    if total == 0 {
+        // VV: Drawing no cubes at all is certain
+        return 0.0;
+    }
+    let ln_shares = min_bag
+        .cubes
+        .keys()
+        .filter(|colour| drawn(colour) > 0)
+        .map(|colour| (colour, (drawn(colour) as f64 / total as f64).ln()))
+        .collect::<Vec<_>>();
+
+    game.rounds
+        .iter()
+        .map(|round| {
+            ln_fact.get(round.total())
+                + ln_shares
+                    .iter()
+                    .map(|(colour, ln_share)| {
+                        let number = round.count(colour);
+                        number as f64 * ln_share - ln_fact.get(number)
+                    })
+                    .sum::<f64>()
+        })
+        .sum()
+}
+
+fn ln_sum_exp(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max == f64::NEG_INFINITY {
+        return max;
+    }
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+/// Calls @visit with every bag that holds exactly @extra cubes more than @min_bag
+fn for_each_bag(min_bag: &Round, extra: u32, visit: &mut impl FnMut(&Round)) {
+    fn distribute(
+        colours: &[String],
+        remaining: u32,
+        bag: &mut Round,
+        visit: &mut impl FnMut(&Round),
+    ) {
+        match colours {
+            [] => visit(bag),
+            [last] => {
+                *bag.cubes.get_mut(last).unwrap() += remaining;
+                visit(bag);
+                *bag.cubes.get_mut(last).unwrap() -= remaining;
+            }
+            [first, rest @ ..] => {
+                for added in 0..=remaining {
+                    *bag.cubes.get_mut(first).unwrap() += added;
+                    distribute(rest, remaining - added, bag, visit);
+                    *bag.cubes.get_mut(first).unwrap() -= added;
+                }
+            }
+        }
+    }
+
+    let colours = min_bag.cubes.keys().cloned().collect::<Vec<_>>();
+    if colours.is_empty() && extra > 0 {
+        return;
+    }
+    distribute(&colours, extra, &mut min_bag.clone(), visit);
+}
+
+/// Estimates the bag behind @game assuming that it only holds colours which the game draws.
+/// Considers every bag which is at least as large as the minimum bag and at most @config.max_extra
+/// cubes larger.
+pub fn infer(game: &Game, config: &Config) -> Inference {
+    let min_bag = game.min_bag();
+    let min_size = min_bag.total();
+    let colours = min_bag.cubes.len() as u32;
+    let ln_fact = LnFactorial::new(min_size + config.max_extra + colours);
+
+    let mut best = (min_bag.clone(), f64::NEG_INFINITY);
+    let mut ln_evidence = vec![];
+
+    for extra in 0..=config.max_extra {
+        let size = min_size + extra;
+        let mut ln_likelihoods = vec![];
+
+        for_each_bag(&min_bag, extra, &mut |bag| {
+            let ln_l = ln_likelihood(game, bag, &ln_fact);
+            // VV: On ties prefer the smaller bag, we visit those first
+            if ln_l > best.1 + 1e-12 {
+                best = (bag.clone(), ln_l);
+            }
+            ln_likelihoods.push(ln_l);
+        });
+
+        if ln_likelihoods.is_empty() {
+            break;
+        }
+
+        // VV: Bags which cannot produce the game have a likelihood of 0 but they still count
+        // towards the number of ways to split @size cubes between the colours
+        let splits = if colours == 0 {
+            0.0
+        } else {
+            ln_fact.ln_choose(size + colours - 1, colours - 1)
+        };
+
+        ln_evidence.push((
+            size,
+            config.prior.ln_weight(size, &ln_fact) + ln_sum_exp(&ln_likelihoods) - splits,
+        ));
+    }
+
+    let ln_norm = ln_sum_exp(&ln_evidence.iter().map(|(_, v)| *v).collect::<Vec<_>>());
+    let posterior = ln_evidence
+        .into_iter()
+        .map(|(size, v)| (size, (v - ln_norm).exp()))
+        .collect();
+
+    Inference {
+        id: game.id,
+        at_limit: config.max_extra > 0 && best.0.total() == min_size + config.max_extra,
+        best: best.0,
+        ln_likelihood: best.1,
+        ln_unbounded: ln_unbounded(game, &ln_fact),
+        posterior,
+    }
+}
@@ -0,0 +1,4 @@
+pub mod analysis;
+#[path = "../../a/src/game.rs"]
+pub mod game;
+pub mod inference;
@@ -1,109 +1,123 @@
-use anyhow::{bail, Context, Result};
-use clap::{arg, Parser};
-use std::io::BufRead;
+use anyhow::{Context, Result};
+use b::analysis::{analyse, write_colours_csv, write_games_csv};
+use b::game::{colours_of, parse_bag_path, parse_path, Game, Round};
+use b::inference::{infer, Config, Inference, Prior};
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(version, about)]
 struct Args {
-    #[arg(short, long, default_value = "input/mine")]
+    #[arg(short, long, default_value = "input/mine", global = true)]
     input: std::path::PathBuf,
-}
 
-struct Round {
-    blue: u32,
-    red: u32,
-    green: u32,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-struct Game {
-    id: u32,
-    rounds: Vec<Round>,
-}
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Estimate the contents of the bag behind each game assuming that every round draws its
+    /// cubes without replacement and puts them back afterwards
+    Infer {
+        /// How many cubes beyond the minimum bag of a game to consider
+        #[arg(long, default_value_t = 30)]
+        max_extra: u32,
 
-fn parse_reader(mut reader: Box<dyn std::io::BufRead>) -> Result<Vec<Game>> {
-    let mut ret = vec![];
-    for line in reader.lines() {
-        let line = line?;
-        let line = line.trim();
+        /// The prior over the number of cubes in the bag
+        #[arg(long, value_enum, default_value_t = PriorKind::Uniform)]
+        prior: PriorKind,
 
-        if line.len() == 0 {
-            continue;
-        }
+        /// The mean of the poisson prior, which must be positive
+        #[arg(long, default_value_t = 39.0)]
+        prior_mean: f64,
 
-        if !line.starts_with("Game ") {
-            bail!("Line {line} does not start with \"Game \"");
-        }
-        let line = &line[5..];
-        let mut parts = line.split(':');
-        let id = parts.next().with_context(|| "Part of line containing ID")?;
-        let id = id.parse::<u32>().with_context(|| "Parsing ID of game")?;
-        let mut rounds = vec![];
-        let remaining = parts
-            .next()
-            .with_context(|| "Part of line containing rounds")?
-            .trim();
-
-        for part in remaining.split("; ") {
-            let mut round = Round {
-                blue: 0,
-                red: 0,
-                green: 0,
-            };
+        /// How many of the most and least surprising games to report
+        #[arg(long, default_value_t = 3)]
+        top: usize,
+    },
 
-            for cubes in part.split(", ") {
-                let mut number_colour = cubes.split(" ");
-                let number = number_colour.next().with_context(|| "Number in round")?;
-                let colour = number_colour.next().with_context(|| "Colour in round")?;
-                let number = number
-                    .parse::<u32>()
-                    .with_context(|| "Invalid number of cubes")?;
-
-                match colour {
-                    "blue" => round.blue = number,
-                    "red" => round.red = number,
-                    "green" => round.green = number,
-                    what => bail!("Invalid colour {what}"),
-                }
-            }
+    /// Print statistics for every game and colour
+    Analyse {
+        /// A bag to check the games against, using the same syntax as a round
+        /// e.g. "12 red, 13 green, 14 blue". Can be repeated
+        #[arg(short, long)]
+        bag: Vec<String>,
 
-            rounds.push(round);
-        }
+        /// A file with a bag to check the games against, one or more "<number> <colour>"
+        /// entries per line. Can be repeated
+        #[arg(long)]
+        bag_file: Vec<std::path::PathBuf>,
 
-        ret.push(Game { id, rounds })
-    }
+        #[arg(short, long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
 
-    Ok(ret)
+        /// Which table to print in CSV format, the JSON format contains both
+        #[arg(short, long, value_enum, default_value_t = Table::Games)]
+        table: Table,
+    },
 }
 
-fn parse_str(text: &str) -> Result<Vec<Game>> {
-    let cursor = std::io::Cursor::new(text.to_string());
-    let reader = std::io::BufReader::new(cursor);
-    parse_reader(Box::new(reader))
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Json,
 }
 
-fn parse_path(path: &std::path::Path) -> Result<Vec<Game>> {
-    let file = std::fs::File::open(path)
-        .with_context(|| format!("Could not open input file {0}", path.display()))?;
-    let reader = std::io::BufReader::new(file);
-    parse_reader(Box::new(reader))
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Table {
+    Games,
+    Colours,
 }
 
-fn find_min_cubes(games: &Vec<Game>) -> u128 {
-    games.iter().fold(0u128, |acc, game| {
-        let min_cubes = game.rounds.iter().fold(
-            Round {
-                red: 0,
-                blue: 0,
-                green: 0,
-            },
-            |acc, round| Round {
-                red: acc.red.max(round.red),
-                green: acc.green.max(round.green),
-                blue: acc.blue.max(round.blue),
-            },
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PriorKind {
+    Uniform,
+    Poisson,
+}
+
+/// The sum of the powers of the minimum bags over every colour that the games draw
+fn find_min_cubes(games: &[Game]) -> u128 {
+    let colours = colours_of(games);
+    games
+        .iter()
+        .map(|game| game.min_bag().power(&colours))
+        .sum()
+}
+
+fn report_inference(games: &[Game], config: &Config, top: usize) {
+    let mut inferences = games
+        .iter()
+        .map(|game| infer(game, config))
+        .collect::<Vec<_>>();
+
+    for inference in &inferences {
+        let limit = if inference.at_limit {
+            " (at the --max-extra limit, larger bags are likelier still)"
+        } else {
+            ""
+        };
+        println!(
+            "Game {}: most likely bag {} ({} cubes){limit}, surprise {:.2} bits, \
+             posterior size mode {} mean {:.2}",
+            inference.id,
+            inference.best,
+            inference.best.total(),
+            inference.surprise(),
+            inference.posterior_mode(),
+            inference.posterior_mean(),
         );
-        (min_cubes.red * min_cubes.green * min_cubes.blue) as u128 + acc
-    })
+    }
+
+    inferences.sort_by(|a, b| b.surprise().total_cmp(&a.surprise()));
+
+    let describe = |inference: &Inference| {
+        println!("  Game {}: {:.2} bits", inference.id, inference.surprise())
+    };
+
+    println!("Most surprising games:");
+    inferences.iter().take(top).for_each(describe);
+    println!("Least surprising games:");
+    inferences.iter().rev().take(top).for_each(describe);
 }
 
 #[test]
@@ -114,7 +128,7 @@ Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
 Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
 Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
 
-    let games = parse_str(sample)?;
+    let games = b::game::parse_str(sample)?;
 
     let solution = find_min_cubes(&games);
 
@@ -122,6 +136,170 @@ Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green";
     Ok(())
 }
 
+#[test]
+fn test_any_colour() -> Result<()> {
+    let games = b::game::parse_str("Game 7: 3 cyan, 4 red; 5 cyan, 2 magenta; 1 red")?;
+
+    assert_eq!(games[0].min_bag(), "5 cyan, 4 red, 2 magenta".parse()?);
+    assert_eq!(find_min_cubes(&games), 40);
+    Ok(())
+}
+
+#[test]
+fn test_missing_colour() -> Result<()> {
+    // VV: Game 1 never draws green so its minimum bag has 0 green cubes and a power of 0
+    let games = b::game::parse_str(
+        "Game 1: 3 blue, 4 red; 1 red, 6 blue
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red",
+    )?;
+
+    assert_eq!(find_min_cubes(&games), 12);
+    assert_eq!(b::analysis::analyse(&games, &[]).games[0].power, 0);
+    Ok(())
+}
+
+#[test]
+fn test_infer() -> Result<()> {
+    let games = b::game::parse_str(
+        "Game 1: 1 red, 1 blue; 1 red, 1 blue
+Game 2: 2 red; 2 blue
+Game 3: 4 green",
+    )?;
+    let config = Config {
+        max_extra: 10,
+        prior: Prior::Uniform,
+    };
+
+    // VV: Drawing all the cubes in the bag is certain
+    let inference = infer(&games[0], &config);
+    assert_eq!(inference.best, "1 red, 1 blue".parse()?);
+    assert!(inference.surprise().abs() < 1e-9);
+
+    // VV: Drawing 2 cubes of the same colour gets likelier as the bag grows so the best bag is
+    // the largest balanced one the search allows. The surprise uses the limit of an endless
+    // balanced bag which draws 2 red cubes 1/4 of the time, whatever the limit.
+    let inference = infer(&games[1], &config);
+    assert_eq!(inference.best, "7 red, 7 blue".parse()?);
+    assert!(inference.at_limit);
+    assert!((inference.surprise() - 4.0).abs() < 1e-9);
+    let wider = infer(
+        &games[1],
+        &Config {
+            max_extra: 20,
+            ..config
+        },
+    );
+    assert!(wider.at_limit);
+    assert!((wider.surprise() - inference.surprise()).abs() < 1e-9);
+    assert!(!infer(&games[0], &config).at_limit);
+
+    let total = inference.posterior.iter().map(|(_, p)| p).sum::<f64>();
+    assert!((total - 1.0).abs() < 1e-9);
+    // VV: 1/36 for the only feasible bag of 4 cubes averaged over the 5 ways to split 4 cubes,
+    // vs 2 * 3/100 for the 2 feasible bags of 5 cubes averaged over 6 ways to split 5 cubes
+    assert_eq!(inference.posterior[0].0, 4);
+    let ratio = inference.posterior[1].1 / inference.posterior[0].1;
+    assert!((ratio - (6.0 / 100.0 / 6.0) / (1.0 / 36.0 / 5.0)).abs() < 1e-9);
+
+    // VV: With a single colour every bag explains the game equally well
+    let inference = infer(&games[2], &config);
+    assert_eq!(inference.posterior.len(), 11);
+    assert!(inference
+        .posterior
+        .iter()
+        .all(|(_, p)| (p - 1.0 / 11.0).abs() < 1e-9));
+
+    assert!(Prior::poisson(0.0).is_err());
+    assert!(Prior::poisson(-1.0).is_err());
+    assert!(Prior::poisson(f64::NAN).is_err());
+    let inference = infer(
+        &games[0],
+        &Config {
+            prior: Prior::poisson(3.0)?,
+            ..config
+        },
+    );
+    assert!(inference.posterior.iter().all(|(_, p)| p.is_finite()));
+
+    // VV: A colour drawn 0 times, and a game which draws no cubes, are certain rather than NaN
+    let games = b::game::parse_str("Game 4: 0 red, 2 blue; 1 blue\nGame 5: 0 red; 0 blue")?;
+    for game in &games {
+        let inference = infer(game, &config);
+        assert!(inference.ln_unbounded.abs() < 1e-9);
+        assert!(inference.surprise().abs() < 1e-9);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_analyse() -> Result<()> {
+    let games = b::game::parse_str(
+        "Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red",
+    )?;
+    let bags = [
+        "12 red, 13 green, 14 blue".parse()?,
+        "20 red, 13 green, 6 blue".parse()?,
+    ];
+    let analysis = analyse(&games, &bags);
+
+    assert_eq!(analysis.games[0].rounds, 3);
+    assert_eq!(analysis.games[0].power, 48);
+    assert_eq!(analysis.games[0].colours["blue"].max, 6);
+    assert_eq!(analysis.games[0].colours["blue"].mean, 3.0);
+    assert_eq!(analysis.games[0].feasible, [true, true]);
+    assert_eq!(analysis.games[2].feasible, [false, true]);
+
+    assert_eq!(analysis.colours[0].colour, "blue");
+    assert_eq!(analysis.colours[2].games, 3);
+    assert_eq!(analysis.colours[2].max, 20);
+    assert_eq!(analysis.colours[2].mean, 31.0 / 9.0);
+
+    let mut csv = vec![];
+    write_games_csv(&mut csv, &analysis)?;
+    let csv = String::from_utf8(csv)?;
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some(
+            "game,rounds,power,max blue,mean blue,max green,mean green,max red,mean red,\
+             \"feasible with 14 blue, 13 green, 12 red\",\"feasible with 6 blue, 13 green, 20 red\""
+        )
+    );
+    assert_eq!(
+        lines.next(),
+        Some("1,3,48,6,3,2,1.3333333333333333,4,1.6666666666666667,true,true")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_analyse_without_rounds() -> Result<()> {
+    let games = [Game {
+        id: 4,
+        rounds: vec![],
+    }];
+    let analysis = analyse(&games, &[]);
+    assert_eq!(analysis.games[0].rounds, 0);
+
+    let empty = analyse(&[], &[]);
+    let mut csv = vec![];
+    write_games_csv(&mut csv, &analysis)?;
+    write_colours_csv(&mut csv, &empty)?;
+    assert!(!String::from_utf8(csv)?.contains("NaN"));
+
+    let summaries = b::analysis::colour_summaries(&[Game {
+        id: 5,
+        rounds: vec!["1 red".parse()?],
+    }]);
+    assert_eq!(summaries[0].mean, 1.0);
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
@@ -129,9 +307,51 @@ fn main() -> Result<()> {
     let path = cwd.join(&args.input);
 
     let games = parse_path(&path)?;
-    let solution = find_min_cubes(&games);
 
-    println!("{solution}");
+    match args.command {
+        None => {
+            let solution = find_min_cubes(&games);
+            println!("{solution}");
+        }
+        Some(Command::Infer {
+            max_extra,
+            prior,
+            prior_mean,
+            top,
+        }) => {
+            let prior = match prior {
+                PriorKind::Uniform => Prior::Uniform,
+                PriorKind::Poisson => Prior::poisson(prior_mean)?,
+            };
+            report_inference(&games, &Config { max_extra, prior }, top);
+        }
+        Some(Command::Analyse {
+            bag,
+            bag_file,
+            format,
+            table,
+        }) => {
+            let mut bags = bag
+                .iter()
+                .map(|bag| bag.parse::<Round>().with_context(|| "Parsing --bag"))
+                .collect::<Result<Vec<_>>>()?;
+            for path in &bag_file {
+                bags.push(parse_bag_path(&cwd.join(path))?);
+            }
+            if bags.is_empty() {
+                bags.push("12 red, 13 green, 14 blue".parse()?);
+            }
+
+            let analysis = analyse(&games, &bags);
+            let out = &mut std::io::stdout();
+
+            match (format, table) {
+                (Format::Json, _) => println!("{}", serde_json::to_string_pretty(&analysis)?),
+                (Format::Csv, Table::Games) => write_games_csv(out, &analysis)?,
+                (Format::Csv, Table::Colours) => write_colours_csv(out, &analysis)?,
+            }
+        }
+    }
 
     Ok(())
 }
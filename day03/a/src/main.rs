@@ -1,16 +1,37 @@
+use a::render::{render_ansi, render_html};
+use a::schematic::{Metric, Neighbourhood};
 use anyhow::Result;
-use clap::{arg, command, Parser};
+use clap::{Parser, ValueEnum};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Render {
+    Ansi,
+    Html,
+}
 
 #[derive(Clone, Parser)]
 #[command(version, about)]
 struct Args {
     #[arg(long, short, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    /// How far from a number a symbol may be to count as adjacent
+    #[arg(long, default_value_t = 1)]
+    radius: usize,
+
+    /// How to measure distances: chebyshev, manhattan or orthogonal
+    #[arg(long, default_value = "chebyshev")]
+    metric: Metric,
+
+    /// Print the schematic with parts, non-parts, symbols and gears highlighted instead of
+    /// solving the puzzle
+    #[arg(long, value_enum)]
+    render: Option<Render>,
 }
 
 fn solve(engine: &a::schematic::Schematic) -> u128 {
     engine.parts.iter().fold(0, |sum, part| {
-        if part.symbols.len() > 0 {
+        if !part.symbols.is_empty() {
             sum + part.number as u128
         } else {
             sum
@@ -38,16 +59,120 @@ fn test_sample() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_ragged_unicode() -> Result<()> {
+    // VV: CRLF line endings, trailing whitespace, lines of different lengths and a multi-byte
+    // symbol before a number
+    let sample = "467..114\r\n...€  \r\n..35..633.\r\n......\r\n617×\n";
+    let engine = a::schematic::Schematic::parse_str(sample)?;
+
+    assert_eq!(engine.width, 10);
+    assert_eq!(engine.height, 5);
+    assert_eq!(solve(&engine), 467 + 35 + 617);
+    assert_eq!(engine.part_at(1, 4).unwrap().symbols[0].symbol.label, '×');
+
+    let error = a::schematic::Schematic::parse_str("1..\n.\t*").unwrap_err();
+    assert_eq!(error.to_string(), "Unexpected '\\t' at (1, 1)");
+
+    let error = a::schematic::Schematic::parse_str("..99999999999").unwrap_err();
+    assert_eq!(error.to_string(), "Number at (2, 0) does not fit in a u32");
+
+    Ok(())
+}
+
+#[test]
+fn test_neighbourhood() -> Result<()> {
+    let sample = "12...
+..&..
+....7
+#.3..";
+    let parse = |radius, metric| {
+        a::schematic::Schematic::parse_str_with(sample, Neighbourhood { radius, metric })
+    };
+
+    assert_eq!(solve(&parse(1, Metric::Chebyshev)?), 12);
+    assert_eq!(solve(&parse(2, Metric::Chebyshev)?), 12 + 7 + 3);
+    assert_eq!(solve(&parse(1, Metric::Manhattan)?), 0);
+    assert_eq!(solve(&parse(2, Metric::Manhattan)?), 12 + 3);
+    assert_eq!(solve(&parse(2, Metric::Orthogonal)?), 3);
+    // VV: The '&' is diagonal to the 12 no matter the radius but the '#' is 3 cells below it
+    assert_eq!(solve(&parse(3, Metric::Orthogonal)?), 12 + 3);
+
+    let engine = parse(3, Metric::Manhattan)?;
+    let distances = engine
+        .part_at(4, 2)
+        .unwrap()
+        .symbols
+        .iter()
+        .map(|nearby| (nearby.symbol.label, nearby.distance))
+        .collect::<Vec<_>>();
+    assert_eq!(distances, [('&', 3)]);
+
+    let near = engine
+        .parts_near(2, 1)
+        .into_iter()
+        .map(|(part, distance)| (part.number, distance))
+        .collect::<Vec<_>>();
+    assert_eq!(near, [(12, 2), (7, 3), (3, 2)]);
+
+    Ok(())
+}
+
+#[test]
+fn test_render() -> Result<()> {
+    let engine = a::schematic::Schematic::parse_str("467...1\n...*<.\n..35..")?;
+
+    assert_eq!(
+        render_ansi(&engine),
+        "\x1b[1;30;45m467\x1b[0m...\x1b[2;31m1\x1b[0m\n\
+         ...\x1b[1;30;45m*\x1b[0m\x1b[33m<\x1b[0m.\n\
+         ..\x1b[1;30;45m35\x1b[0m..\n"
+    );
+
+    let html = render_html(&engine);
+    assert!(html
+        .contains(r#"<span class="gear" data-gear="g0" title="gear 467 × 35 = 16345">*</span>"#));
+    assert!(html.contains(
+        r#"<span class="factor" data-gear="g0" title="part 35 next to * &lt;">35</span>"#
+    ));
+    assert!(html.contains(r#"<span class="non-part" title="1 is not a part">1</span>"#));
+    assert!(
+        html.contains(r#"<span class="symbol" title="symbol next to 1 parts, sum 35">&lt;</span>"#)
+    );
+
+    // VV: Numbers keep their leading zeros so the rest of the row stays in place
+    let engine = a::schematic::Schematic::parse_str("007.#\n.0042")?;
+    assert_eq!(
+        render_ansi(&engine),
+        "\x1b[2;31m007\x1b[0m.\x1b[33m#\x1b[0m\n\
+         .\x1b[32m0042\x1b[0m\n"
+    );
+    let html = render_html(&engine);
+    assert!(html.contains(r#"<span class="non-part" title="7 is not a part">007</span>"#));
+    assert!(html.contains(r#"<span class="part" title="part 42 next to #">0042</span>"#));
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let cwd = std::env::current_dir()?;
     let args = Args::parse();
 
     let path = cwd.join(args.input);
-    let engine = a::schematic::Schematic::parse_path(&path)?;
-
-    let solution = solve(&engine);
+    let neighbourhood = Neighbourhood {
+        radius: args.radius,
+        metric: args.metric,
+    };
+    let engine = a::schematic::Schematic::parse_path_with(&path, neighbourhood)?;
 
-    println!("{solution}");
+    match args.render {
+        Some(Render::Ansi) => print!("{}", render_ansi(&engine)),
+        Some(Render::Html) => print!("{}", render_html(&engine)),
+        None => {
+            let solution = solve(&engine);
+            println!("{solution}");
+        }
+    }
 
     Ok(())
 }
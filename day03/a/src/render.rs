@@ -0,0 +1,272 @@
+use crate::schematic::{Part, Schematic, Symbol};
+use std::collections::HashMap;
+
+/// A '*' next to exactly 2 parts
+struct Gear {
+    symbol: Symbol,
+    factors: [u32; 2],
+}
+
+/// What a cell of the schematic is, numbers are described by the cell of their first digit
+enum Cell<'a> {
+    Empty(char),
+    /// A part and the indices of the gears it is a factor of
+    Part(&'a Part, &'a [usize]),
+    NonPart(&'a Part),
+    Symbol(&'a Symbol),
+    Gear(usize),
+}
+
+struct Layout<'a> {
+    schematic: &'a Schematic,
+    gears: Vec<Gear>,
+    /// Maps the (x, y) of the first digit of a part to the indices of the gears it is a factor of
+    factor_of: HashMap<(usize, usize), Vec<usize>>,
+    /// Maps the (x, y) of a gear to its index in @gears
+    gear_at: HashMap<(usize, usize), usize>,
+}
+
+impl<'a> Layout<'a> {
+    fn new(schematic: &'a Schematic) -> Self {
+        let mut gears = vec![];
+        let mut factor_of = HashMap::new();
+        let mut gear_at = HashMap::new();
+
+        for symbol in schematic.symbols.iter().filter(|s| s.label == '*') {
+            let parts = schematic.parts_adjacent_to(symbol.x, symbol.y);
+            if parts.len() != 2 {
+                continue;
+            }
+
+            for part in &parts {
+                factor_of
+                    .entry((part.x, part.y))
+                    .or_insert_with(Vec::new)
+                    .push(gears.len());
+            }
+            gear_at.insert((symbol.x, symbol.y), gears.len());
+            gears.push(Gear {
+                symbol: *symbol,
+                factors: [parts[0].number, parts[1].number],
+            });
+        }
+
+        Self {
+            schematic,
+            gears,
+            factor_of,
+            gear_at,
+        }
+    }
+
+    /// The digits of @part exactly as they are written in the schematic e.g. with leading zeros
+    fn digits(&self, part: &Part) -> String {
+        (part.x..part.x + part.len)
+            .map(|x| self.schematic.cell(x, part.y))
+            .collect()
+    }
+
+    /// The cells of every row, every number is a single cell
+    fn rows(&self) -> Vec<Vec<Cell<'_>>> {
+        let schematic = self.schematic;
+
+        schematic
+            .plan
+            .lines()
+            .enumerate()
+            .map(|(y, line)| {
+                let mut cells = vec![];
+                let mut x = 0;
+                let width = line.chars().count();
+
+                while x < width {
+                    if let Some(part) = schematic.part_at(x, y) {
+                        cells.push(if part.symbols.is_empty() {
+                            Cell::NonPart(part)
+                        } else {
+                            let gears = self.factor_of.get(&(part.x, part.y));
+                            Cell::Part(part, gears.map_or(&[], |gears| gears.as_slice()))
+                        });
+                        x += part.len;
+                        continue;
+                    }
+
+                    cells.push(
+                        match (self.gear_at.get(&(x, y)), schematic.symbol_at(x, y)) {
+                            (Some(gear), _) => Cell::Gear(*gear),
+                            (None, Some(symbol)) => Cell::Symbol(symbol),
+                            (None, None) => Cell::Empty(schematic.cell(x, y)),
+                        },
+                    );
+                    x += 1;
+                }
+
+                cells
+            })
+            .collect()
+    }
+}
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_PART: &str = "\x1b[32m";
+const ANSI_NON_PART: &str = "\x1b[2;31m";
+const ANSI_SYMBOL: &str = "\x1b[33m";
+/// Each gear and its 2 factors share one of these colours
+const ANSI_GEARS: [&str; 6] = [
+    "\x1b[1;30;45m",
+    "\x1b[1;30;46m",
+    "\x1b[1;30;44m",
+    "\x1b[1;30;43m",
+    "\x1b[1;30;42m",
+    "\x1b[1;30;41m",
+];
+
+/// Renders the schematic for a terminal. Parts are green, numbers which are not parts are dim red,
+/// symbols are yellow, and each gear is highlighted along with its 2 factors.
+pub fn render_ansi(schematic: &Schematic) -> String {
+    let layout = Layout::new(schematic);
+    let mut out = String::new();
+
+    for row in layout.rows() {
+        for cell in row {
+            let (colour, text) = match cell {
+                Cell::Empty(label) => (None, label.to_string()),
+                Cell::Part(part, []) => (Some(ANSI_PART), layout.digits(part)),
+                Cell::Part(part, [gear, ..]) => (
+                    Some(ANSI_GEARS[gear % ANSI_GEARS.len()]),
+                    layout.digits(part),
+                ),
+                Cell::NonPart(part) => (Some(ANSI_NON_PART), layout.digits(part)),
+                Cell::Symbol(symbol) => (Some(ANSI_SYMBOL), symbol.label.to_string()),
+                Cell::Gear(gear) => (
+                    Some(ANSI_GEARS[gear % ANSI_GEARS.len()]),
+                    layout.gears[gear].symbol.label.to_string(),
+                ),
+            };
+
+            match colour {
+                Some(colour) => out.push_str(&format!("{colour}{text}{ANSI_RESET}")),
+                None => out.push_str(&text),
+            }
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.chars()
+        .map(|c| match c {
+            '<' => "&lt;".to_string(),
+            '>' => "&gt;".to_string(),
+            '&' => "&amp;".to_string(),
+            '"' => "&quot;".to_string(),
+            '\'' => "&#39;".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+const HTML_HEAD: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Engine schematic</title>
+<style>
+body { background: #1e1e1e; color: #777; }
+pre { font-size: 16px; line-height: 1.2; }
+.part { color: #6c6; }
+.non-part { color: #c66; text-decoration: line-through; }
+.symbol { color: #dd6; }
+.gear { color: #f6f; font-weight: bold; }
+.factor { color: #6cf; }
+.linked { background: #555; outline: 1px solid #f6f; }
+</style>
+</head>
+<body>
+<pre>
+"#;
+
+const HTML_TAIL: &str = r#"</pre>
+<script>
+for (const cell of document.querySelectorAll("[data-gear]")) {
+  const linked = cell.dataset.gear
+    .split(" ")
+    .flatMap((gear) => [...document.querySelectorAll(`[data-gear~="${gear}"]`)]);
+  cell.addEventListener("mouseenter", () => linked.forEach((c) => c.classList.add("linked")));
+  cell.addEventListener("mouseleave", () => linked.forEach((c) => c.classList.remove("linked")));
+}
+</script>
+</body>
+</html>
+"#;
+
+/// Renders the schematic as a standalone HTML page. Hovering over a number or symbol shows its
+/// value and hovering over a gear or one of its factors highlights all 3.
+pub fn render_html(schematic: &Schematic) -> String {
+    let layout = Layout::new(schematic);
+    let mut out = HTML_HEAD.to_string();
+
+    for row in layout.rows() {
+        for cell in row {
+            let span = match cell {
+                Cell::Empty(label) => escape_html(&label.to_string()),
+                Cell::Part(part, gears) => {
+                    let labels = part
+                        .symbols
+                        .iter()
+                        .map(|nearby| nearby.symbol.label.to_string())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                    let title = format!("part {} next to {}", part.number, labels);
+                    if gears.is_empty() {
+                        format!(
+                            r#"<span class="part" title="{}">{}</span>"#,
+                            escape_html(&title),
+                            layout.digits(part)
+                        )
+                    } else {
+                        let gears = gears
+                            .iter()
+                            .map(|gear| format!("g{gear}"))
+                            .collect::<Vec<_>>()
+                            .join(" ");
+                        format!(
+                            r#"<span class="factor" data-gear="{gears}" title="{}">{}</span>"#,
+                            escape_html(&title),
+                            layout.digits(part)
+                        )
+                    }
+                }
+                Cell::NonPart(part) => format!(
+                    r#"<span class="non-part" title="{} is not a part">{}</span>"#,
+                    part.number,
+                    layout.digits(part)
+                ),
+                Cell::Symbol(symbol) => {
+                    let parts = schematic.parts_adjacent_to(symbol.x, symbol.y);
+                    format!(
+                        r#"<span class="symbol" title="symbol next to {} parts, sum {}">{}</span>"#,
+                        parts.len(),
+                        parts.iter().map(|part| part.number as u128).sum::<u128>(),
+                        escape_html(&symbol.label.to_string())
+                    )
+                }
+                Cell::Gear(gear) => {
+                    let [left, right] = layout.gears[gear].factors;
+                    format!(
+                        r#"<span class="gear" data-gear="g{gear}" title="gear {left} × {right} = {}">{}</span>"#,
+                        left as u128 * right as u128,
+                        escape_html(&layout.gears[gear].symbol.label.to_string())
+                    )
+                }
+            };
+            out.push_str(&span);
+        }
+        out.push('\n');
+    }
+
+    out.push_str(HTML_TAIL);
+    out
+}
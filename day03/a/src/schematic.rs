@@ -1,7 +1,7 @@
-use anyhow::{Context, Result};
-use regex::Regex;
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Symbol {
     pub x: usize,
     pub y: usize,
@@ -11,8 +11,109 @@ pub struct Symbol {
 #[derive(Debug)]
 pub struct Part {
     pub number: u32,
-    pub symbols: Vec<Symbol>,
+    /// The column of the first digit
+    pub x: usize,
+    pub y: usize,
+    /// The number of digits
+    pub len: usize,
+    /// The symbols in the neighbourhood of the number
+    pub symbols: Vec<NearbySymbol>,
+}
+
+impl Part {
+    fn span(&self) -> (usize, usize, usize) {
+        (self.x, self.x + self.len, self.y)
+    }
+}
+
+/// A symbol within the neighbourhood of a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NearbySymbol {
+    pub symbol: Symbol,
+    /// The distance to the closest digit of the number
+    pub distance: usize,
+}
+
+/// How to measure the distance between a cell and a number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// Diagonal steps count as 1
+    Chebyshev,
+    /// Diagonal steps count as 2
+    Manhattan,
+    /// Like Manhattan but the cell must be on the same row or column as a digit
+    Orthogonal,
+}
+
+impl std::str::FromStr for Metric {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        Ok(match text {
+            "chebyshev" => Metric::Chebyshev,
+            "manhattan" => Metric::Manhattan,
+            "orthogonal" => Metric::Orthogonal,
+            what => bail!("Unknown metric {what}"),
+        })
+    }
+}
+
+/// The cells around a number which count as adjacent to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Neighbourhood {
+    pub radius: usize,
+    pub metric: Metric,
+}
+
+impl Default for Neighbourhood {
+    /// The 8 cells around each digit, like the puzzle expects
+    fn default() -> Self {
+        Self {
+            radius: 1,
+            metric: Metric::Chebyshev,
+        }
+    }
 }
+
+impl Neighbourhood {
+    /// The distance between (@x, @y) and the closest cell of the columns @start..@end of row
+    /// @row. None if (@x, @y) is outside the neighbourhood or inside the span.
+    fn distance(
+        &self,
+        (x, y): (usize, usize),
+        (start, end, row): (usize, usize, usize),
+    ) -> Option<usize> {
+        let dx = if x < start {
+            start - x
+        } else if x >= end {
+            x + 1 - end
+        } else {
+            0
+        };
+        let dy = y.abs_diff(row);
+
+        let distance = match self.metric {
+            Metric::Chebyshev => dx.max(dy),
+            Metric::Manhattan => dx + dy,
+            Metric::Orthogonal if dx == 0 || dy == 0 => dx + dy,
+            Metric::Orthogonal => return None,
+        };
+
+        (1..=self.radius).contains(&distance).then_some(distance)
+    }
+
+    /// The cells in the neighbourhood of the columns @start..@end of row @row and their distance
+    fn around(
+        &self,
+        (start, end, row): (usize, usize, usize),
+    ) -> impl Iterator<Item = ((usize, usize), usize)> + '_ {
+        let radius = self.radius;
+        (row.saturating_sub(radius)..=row + radius)
+            .flat_map(move |y| (start.saturating_sub(radius)..end + radius).map(move |x| (x, y)))
+            .filter_map(move |cell| Some((cell, self.distance(cell, (start, end, row))?)))
+    }
+}
+
 #[derive(Debug)]
 pub struct Schematic {
     pub plan: String,
@@ -22,6 +123,19 @@ pub struct Schematic {
 
     /// An array containing all numbers and the symbol that they are associated with
     pub parts: Vec<Part>,
+
+    /// Every symbol in the schematic, in reading order
+    pub symbols: Vec<Symbol>,
+
+    /// Decides which symbols are adjacent to which numbers
+    pub neighbourhood: Neighbourhood,
+
+    /// The characters of every line, lines may have different lengths
+    grid: Vec<Vec<char>>,
+    /// Maps the (x, y) of every digit to the index of its Part in @parts
+    digits_index: HashMap<(usize, usize), usize>,
+    /// Maps the (x, y) of every symbol to its index in @symbols
+    symbols_index: HashMap<(usize, usize), usize>,
 }
 
 impl Schematic {
@@ -30,87 +144,237 @@ impl Schematic {
     }
 
     pub fn parse_path(path: &std::path::Path) -> Result<Self> {
-        let contents = std::fs::read_to_string(&path).with_context(|| "Could not read file")?;
-        Self::parse_str(&contents)
+        Self::parse_path_with(path, Neighbourhood::default())
+    }
+
+    pub fn parse_path_with(path: &std::path::Path, neighbourhood: Neighbourhood) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).with_context(|| "Could not read file")?;
+        Self::parse_str_with(&contents, neighbourhood)
     }
 
     pub fn parse_str(text: &str) -> Result<Self> {
-        let text = text.replace('\r', "");
-        let text = text.trim();
-        let lines = text.split('\n');
-        let mut width = 0;
-        let height = text.lines().count();
+        Self::parse_str_with(text, Neighbourhood::default())
+    }
 
-        let mut parts = vec![];
+    /// Parses a schematic into a grid of characters. Trailing whitespace (including the '\r' of
+    /// CRLF line endings) is ignored and short lines behave as if they were padded with '.'.
+    /// Any character which is neither an ASCII digit nor '.' is a symbol, other whitespace and
+    /// control characters are rejected.
+    pub fn parse_str_with(text: &str, neighbourhood: Neighbourhood) -> Result<Self> {
+        let mut grid = text
+            .lines()
+            .map(|line| line.trim_end().chars().collect::<Vec<_>>())
+            .skip_while(|row| row.is_empty())
+            .collect::<Vec<_>>();
 
-        fn scan_lines_for_symbols(
-            number: u32,
-            sub: &str,
-            start_y: usize,
-            start_x: usize,
-            end_x: usize,
-            parts: &mut Vec<Part>,
-        ) {
-            let mut part = Part {
-                number,
-                symbols: vec![],
-            };
-
-            let symbols = &mut part.symbols;
-
-            let lines = sub.split('\n');
-            for (y, line) in lines.into_iter().enumerate() {
-                for (idx, label) in line[start_x..end_x].chars().enumerate() {
-                    if !(('0' <= label && label <= '9') || label == '.' || label == '\n') {
-                        symbols.push(Symbol {
-                            x: idx + start_x,
-                            y: y + start_y,
-                            label,
-                        })
-                    }
+        while grid.last().is_some_and(|row| row.is_empty()) {
+            grid.pop();
+        }
+
+        for (y, row) in grid.iter().enumerate() {
+            for (x, label) in row.iter().enumerate() {
+                if label.is_whitespace() || label.is_control() {
+                    bail!("Unexpected {label:?} at ({x}, {y})");
                 }
             }
-
-            parts.push(part);
         }
 
-        let re = Regex::new(r"\d+")?;
-        let mut y = 0usize;
-        for line in lines {
-            let line = line.trim();
-            if line.len() == 0 {
-                continue;
-            }
+        let width = grid.iter().map(|row| row.len()).max().unwrap_or(0);
+        let height = grid.len();
 
-            width = line.len() + 1;
+        let mut symbols = vec![];
+        let mut parts = vec![];
 
-            let many_matches = re.captures_iter(line);
+        for (y, row) in grid.iter().enumerate() {
+            let mut x = 0;
 
-            for m in many_matches {
-                let m = m.get(0).unwrap();
-                let number = m.as_str().parse::<u32>()?;
-                let start = m.start();
-                let end = m.end();
+            while x < row.len() {
+                let label = row[x];
 
-                let start_x = if start > 0 { start - 1 } else { start };
-                let end_x = if end < width - 1 { end + 1 } else { end };
+                if !label.is_ascii_digit() {
+                    if label != '.' {
+                        symbols.push(Symbol { x, y, label });
+                    }
+                    x += 1;
+                    continue;
+                }
 
-                let start_y = if y > 0 { y - 1 } else { y };
-                let end_y = if y < height - 2 { y + 1 } else { y };
+                let len = row[x..].iter().take_while(|c| c.is_ascii_digit()).count();
+                let number = row[x..x + len]
+                    .iter()
+                    .collect::<String>()
+                    .parse::<u32>()
+                    .with_context(|| format!("Number at ({x}, {y}) does not fit in a u32"))?;
 
-                let end_char = ((end_y + 1) * width).min(text.len());
-                let sub = &text[start_y * width..end_char].trim();
-                scan_lines_for_symbols(number, sub, start_y, start_x, end_x, &mut parts);
+                parts.push(Part {
+                    number,
+                    x,
+                    y,
+                    len,
+                    symbols: vec![],
+                });
+                x += len;
             }
+        }
+
+        let plan = grid
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n");
 
-            y += 1;
+        let mut schematic = Self::with_index(plan, width, height, grid, parts, symbols);
+        schematic.neighbourhood = neighbourhood;
+
+        for idx in 0..schematic.parts.len() {
+            schematic.parts[idx].symbols = schematic.symbols_touching(&schematic.parts[idx]);
         }
 
-        Ok(Self {
-            plan: text.to_string(),
+        Ok(schematic)
+    }
+
+    fn with_index(
+        plan: String,
+        width: usize,
+        height: usize,
+        grid: Vec<Vec<char>>,
+        parts: Vec<Part>,
+        symbols: Vec<Symbol>,
+    ) -> Self {
+        let digits_index = parts
+            .iter()
+            .enumerate()
+            .flat_map(|(idx, part)| (part.x..part.x + part.len).map(move |x| ((x, part.y), idx)))
+            .collect();
+
+        let symbols_index = symbols
+            .iter()
+            .enumerate()
+            .map(|(idx, symbol)| ((symbol.x, symbol.y), idx))
+            .collect();
+
+        Self {
+            plan,
             width,
             height,
             parts,
-        })
+            symbols,
+            neighbourhood: Neighbourhood::default(),
+            grid,
+            digits_index,
+            symbols_index,
+        }
+    }
+
+    /// The character at (@x, @y), cells outside the grid are '.'
+    pub fn cell(&self, x: usize, y: usize) -> char {
+        self.grid
+            .get(y)
+            .and_then(|row| row.get(x))
+            .copied()
+            .unwrap_or('.')
+    }
+
+    /// The part which has a digit at (@x, @y)
+    pub fn part_at(&self, x: usize, y: usize) -> Option<&Part> {
+        self.digits_index.get(&(x, y)).map(|idx| &self.parts[*idx])
+    }
+
+    pub fn symbol_at(&self, x: usize, y: usize) -> Option<&Symbol> {
+        self.symbols_index
+            .get(&(x, y))
+            .map(|idx| &self.symbols[*idx])
+    }
+
+    /// The indices of the parts near (@x, @y) and their distance, in the order they appear in
+    /// @parts
+    fn part_indices_near(&self, x: usize, y: usize) -> Vec<(usize, usize)> {
+        let mut indices = self
+            .neighbourhood
+            .around((x, x + 1, y))
+            .filter_map(|(cell, distance)| Some((*self.digits_index.get(&cell)?, distance)))
+            .collect::<Vec<_>>();
+        indices.sort();
+        indices.dedup_by_key(|(idx, _)| *idx);
+        indices
+    }
+
+    /// All parts in the neighbourhood of (@x, @y), which is typically the location of a symbol,
+    /// and their distance to (@x, @y)
+    pub fn parts_near(&self, x: usize, y: usize) -> Vec<(&Part, usize)> {
+        self.part_indices_near(x, y)
+            .into_iter()
+            .map(|(idx, distance)| (&self.parts[idx], distance))
+            .collect()
+    }
+
+    /// All parts in the neighbourhood of (@x, @y) which is typically the location of a symbol
+    pub fn parts_adjacent_to(&self, x: usize, y: usize) -> Vec<&Part> {
+        self.part_indices_near(x, y)
+            .into_iter()
+            .map(|(idx, _)| &self.parts[idx])
+            .collect()
+    }
+
+    /// All symbols in the neighbourhood of any of the digits of @part
+    pub fn symbols_touching(&self, part: &Part) -> Vec<NearbySymbol> {
+        self.neighbourhood
+            .around(part.span())
+            .filter_map(|((x, y), distance)| {
+                Some(NearbySymbol {
+                    symbol: *self.symbol_at(x, y)?,
+                    distance,
+                })
+            })
+            .collect()
+    }
+
+    /// The numbers which are not next to any symbol
+    pub fn isolated_numbers(&self) -> Vec<&Part> {
+        self.parts
+            .iter()
+            .filter(|part| self.symbols_touching(part).is_empty())
+            .collect()
+    }
+
+    /// Groups of parts which are connected to each other through the symbols that they share.
+    /// Numbers which do not touch any symbol are not part of any cluster.
+    pub fn clusters(&self) -> Vec<Vec<&Part>> {
+        fn find(parent: &mut [usize], idx: usize) -> usize {
+            let mut root = idx;
+            while parent[root] != root {
+                root = parent[root];
+            }
+            parent[idx] = root;
+            root
+        }
+
+        let mut parent = (0..self.parts.len()).collect::<Vec<_>>();
+
+        for symbol in &self.symbols {
+            let indices = self.part_indices_near(symbol.x, symbol.y);
+            for pair in indices.windows(2) {
+                let a = find(&mut parent, pair[0].0);
+                let b = find(&mut parent, pair[1].0);
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+
+        let mut clusters: Vec<Vec<&Part>> = vec![];
+        let mut root_to_cluster = HashMap::new();
+
+        for (idx, part) in self.parts.iter().enumerate() {
+            if self.symbols_touching(part).is_empty() {
+                continue;
+            }
+            let root = find(&mut parent, idx);
+            let cluster = *root_to_cluster.entry(root).or_insert_with(|| {
+                clusters.push(vec![]);
+                clusters.len() - 1
+            });
+            clusters[cluster].push(part);
+        }
+
+        clusters
     }
 }
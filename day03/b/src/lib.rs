@@ -1,2 +1,5 @@
+#[path = "../../a/src/render.rs"]
+pub mod render;
+pub mod rules;
 #[path = "../../a/src/schematic.rs"]
 pub mod schematic;
@@ -1,36 +1,54 @@
-use std::collections::HashMap;
-use anyhow::Result;
-use clap::{arg, command, Parser};
+use anyhow::{Context, Result};
+use b::render::{render_ansi, render_html};
+use b::rules::{parse_rules, Aggregation, Arity, Kind, Rule};
+use b::schematic::{Metric, Neighbourhood};
+use clap::{Parser, ValueEnum};
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Render {
+    Ansi,
+    Html,
+}
 
 #[derive(Clone, Parser)]
 #[command(version, about)]
 struct Args {
     #[arg(long, short, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    /// How far from a number a symbol may be to count as adjacent
+    #[arg(long, default_value_t = 1)]
+    radius: usize,
+
+    /// How to measure distances: chebyshev, manhattan or orthogonal
+    #[arg(long, default_value = "chebyshev")]
+    metric: Metric,
+
+    /// Print the schematic with parts, non-parts, symbols and gears highlighted instead of
+    /// solving the puzzle
+    #[arg(long, value_enum)]
+    render: Option<Render>,
+
+    /// A "<symbol> <arity> <aggregation>" rule to report on instead of solving the puzzle
+    /// e.g. "# 3 sum". The arity is one of any, N, =N, >=N, <=N, N..M and the aggregation is
+    /// one of product, sum, min, max. "<symbol> separator" reports the numbers which only touch
+    /// that symbol and so are not parts. Can be repeated
+    #[arg(long, short)]
+    rule: Vec<String>,
+
+    /// A file with one rule per line, lines starting with "//" are comments
+    #[arg(long)]
+    rules: Option<std::path::PathBuf>,
 }
 
-fn solve(engine: &b::schematic::Schematic) -> u128 {
-    let mut gears: HashMap<(usize, usize), Vec<u32>> = HashMap::new();
-
-    engine.parts.iter().map(|part| {
-        for symbol in &part.symbols {
-            if symbol.label == '*' {
-                if let Some(gear) = gears.get_mut(&(symbol.x, symbol.y)) {
-                    gear.push(part.number);
-                } else {
-                    gears.insert((symbol.x, symbol.y), vec![part.number]);
-                }
-            }
-        }
-    }).count();
+/// A '*' that touches exactly 2 parts is a gear and its ratio is the product of the 2 parts
+const GEAR: Rule = Rule {
+    symbol: '*',
+    kind: Kind::Aggregate(Arity::Exactly(2), Aggregation::Product),
+};
 
-    gears.values().fold(0, |acc, numbers| {
-        if numbers.len() == 2 {
-            acc + (numbers.get(0).unwrap() * numbers.get(1).unwrap()) as u128
-        } else {
-            acc
-        }
-    })
+fn solve(engine: &b::schematic::Schematic) -> Result<u128> {
+    Ok(GEAR.evaluate(engine)?.total)
 }
 
 #[test]
@@ -46,23 +64,178 @@ fn test_sample() -> Result<()> {
 ...$.*....
 .664.598..";
     let engine = b::schematic::Schematic::parse_str(sample)?;
-    let solution = solve(&engine);
+    let solution = solve(&engine)?;
 
     assert_eq!(solution, 467835);
 
     Ok(())
 }
 
+#[test]
+fn test_queries() -> Result<()> {
+    let sample = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+    let engine = b::schematic::Schematic::parse_str(sample)?;
+
+    let numbers = |parts: Vec<&b::schematic::Part>| -> Vec<u32> {
+        parts.iter().map(|part| part.number).collect()
+    };
+
+    assert_eq!(numbers(engine.parts_adjacent_to(3, 1)), [467, 35]);
+    assert_eq!(numbers(engine.parts_adjacent_to(8, 4)), [58]);
+    assert_eq!(numbers(engine.isolated_numbers()), [114, 58]);
+    assert_eq!(engine.part_at(7, 2).map(|part| part.number), Some(633));
+
+    let part = engine.part_at(1, 4).unwrap();
+    assert_eq!(
+        engine.symbols_touching(part),
+        [b::schematic::NearbySymbol {
+            symbol: b::schematic::Symbol {
+                x: 3,
+                y: 4,
+                label: '*'
+            },
+            distance: 1
+        }]
+    );
+
+    let clusters = engine
+        .clusters()
+        .into_iter()
+        .map(numbers)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        clusters,
+        [
+            vec![467, 35],
+            vec![633],
+            vec![617],
+            vec![592],
+            vec![755, 598],
+            vec![664]
+        ]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_rules() -> Result<()> {
+    let sample = "467..114..
+...*......
+..35..633.
+......#...
+617*......
+.....+.58.
+..592.....
+......755.
+...$.*....
+.664.598..";
+    let engine = b::schematic::Schematic::parse_str(sample)?;
+    let rules = parse_rules(
+        "// VV: The gear rule of the puzzle
+* =2 product
+
+* any max
+# >=1 sum
++ 0..1 min
+$ 2 sum",
+    )?;
+
+    let reports = rules
+        .iter()
+        .map(|rule| rule.evaluate(&engine))
+        .map(|report| report.map(|report| (report.matches, report.total)))
+        .collect::<Result<Vec<_>>>()?;
+
+    assert_eq!(
+        reports,
+        [
+            (2, 467835),
+            (3, 467 + 617 + 755),
+            (1, 633),
+            (1, 592),
+            (0, 0)
+        ]
+    );
+
+    assert_eq!(rules[3].to_string(), "+ 0..1 min");
+    assert!("* 2 average".parse::<Rule>().is_err());
+    assert!("** 2 sum".parse::<Rule>().is_err());
+
+    // VV: 12 and 7 only touch separators, 34 also touches a '*' and stays a part
+    let separated = b::schematic::Schematic::parse_str("12%34\n....*\n7%...")?;
+    let separator = "% separator".parse::<Rule>()?;
+    let report = separator.evaluate(&separated)?;
+    assert_eq!((report.matches, report.total), (2, 12 + 7));
+    assert_eq!(separator.to_string(), "% separator");
+    assert!("% separator sum".parse::<Rule>().is_err());
+
+    // VV: Five 10-digit numbers around one symbol multiply past u128::MAX
+    let big = b::schematic::Schematic::parse_str(
+        "4294967295.4294967295\n4294967295*..........\n4294967295.4294967295",
+    )?;
+    assert_eq!(
+        "* any sum".parse::<Rule>()?.evaluate(&big)?.total,
+        5 * 4294967295
+    );
+    let error = "* any product".parse::<Rule>()?.evaluate(&big).unwrap_err();
+    assert_eq!(
+        format!("{error:#}"),
+        "Rule * any product on * at (10, 1): The product overflows"
+    );
+
+    Ok(())
+}
+
 fn main() -> Result<()> {
     let cwd = std::env::current_dir()?;
     let args = Args::parse();
 
     let path = cwd.join(args.input);
-    let engine = b::schematic::Schematic::parse_path(&path)?;
+    let neighbourhood = Neighbourhood {
+        radius: args.radius,
+        metric: args.metric,
+    };
+    let engine = b::schematic::Schematic::parse_path_with(&path, neighbourhood)?;
 
-    let solution = solve(&engine);
+    let mut rules = args
+        .rule
+        .iter()
+        .map(|rule| {
+            rule.parse::<Rule>()
+                .with_context(|| format!("Parsing rule {rule}"))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
-    println!("{solution}");
+    if let Some(path) = &args.rules {
+        let text = std::fs::read_to_string(cwd.join(path))
+            .with_context(|| format!("Could not read rules file {}", path.display()))?;
+        rules.extend(parse_rules(&text)?);
+    }
+
+    if let Some(render) = args.render {
+        match render {
+            Render::Ansi => print!("{}", render_ansi(&engine)),
+            Render::Html => print!("{}", render_html(&engine)),
+        }
+    } else if rules.is_empty() {
+        let solution = solve(&engine)?;
+        println!("{solution}");
+    } else {
+        for rule in &rules {
+            let report = rule.evaluate(&engine)?;
+            println!("{rule}: {} matches, total {}", report.matches, report.total);
+        }
+    }
 
     Ok(())
 }
@@ -0,0 +1,235 @@
+use crate::schematic::Schematic;
+use anyhow::{bail, Context, Result};
+
+/// How many parts a symbol must touch for a Rule to apply to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arity {
+    Any,
+    Exactly(usize),
+    AtLeast(usize),
+    AtMost(usize),
+    /// Inclusive on both ends
+    Between(usize, usize),
+}
+
+impl Arity {
+    pub fn accepts(&self, parts: usize) -> bool {
+        match *self {
+            Arity::Any => true,
+            Arity::Exactly(n) => parts == n,
+            Arity::AtLeast(n) => parts >= n,
+            Arity::AtMost(n) => parts <= n,
+            Arity::Between(low, high) => (low..=high).contains(&parts),
+        }
+    }
+}
+
+impl std::str::FromStr for Arity {
+    type Err = anyhow::Error;
+
+    /// Parses one of: any, N, =N, >=N, <=N, N..M
+    fn from_str(text: &str) -> Result<Self> {
+        let number = |text: &str| {
+            text.parse::<usize>()
+                .with_context(|| format!("Invalid arity {text}"))
+        };
+
+        Ok(if text == "any" {
+            Arity::Any
+        } else if let Some(n) = text.strip_prefix(">=") {
+            Arity::AtLeast(number(n)?)
+        } else if let Some(n) = text.strip_prefix("<=") {
+            Arity::AtMost(number(n)?)
+        } else if let Some(n) = text.strip_prefix('=') {
+            Arity::Exactly(number(n)?)
+        } else if let Some((low, high)) = text.split_once("..") {
+            Arity::Between(number(low)?, number(high)?)
+        } else {
+            Arity::Exactly(number(text)?)
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Product,
+    Sum,
+    Min,
+    Max,
+}
+
+impl Aggregation {
+    /// Combines the numbers of the parts that touch a symbol, no numbers aggregate to 0. Fails
+    /// when the product or sum does not fit in a u128.
+    pub fn apply(&self, numbers: &[u32]) -> Result<u128> {
+        if numbers.is_empty() {
+            return Ok(0);
+        }
+        let mut numbers = numbers.iter().map(|number| *number as u128);
+
+        Ok(match self {
+            Aggregation::Product => numbers
+                .try_fold(1u128, |total, number| total.checked_mul(number))
+                .with_context(|| "The product overflows")?,
+            Aggregation::Sum => numbers
+                .try_fold(0u128, |total, number| total.checked_add(number))
+                .with_context(|| "The sum overflows")?,
+            Aggregation::Min => numbers.min().unwrap_or(0),
+            Aggregation::Max => numbers.max().unwrap_or(0),
+        })
+    }
+}
+
+impl std::str::FromStr for Aggregation {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        Ok(match text {
+            "product" => Aggregation::Product,
+            "sum" => Aggregation::Sum,
+            "min" => Aggregation::Min,
+            "max" => Aggregation::Max,
+            what => bail!("Unknown aggregation {what}"),
+        })
+    }
+}
+
+/// What a Rule does with its symbol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// Combines the parts around each symbol which touches an accepted number of parts
+    Aggregate(Arity, Aggregation),
+    /// The symbol only keeps numbers apart and does not make them parts
+    Separator,
+}
+
+/// A rule looks like "<symbol> <arity> <aggregation>" e.g. "* 2 product" describes gears, or
+/// "<symbol> separator" e.g. "% separator"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub symbol: char,
+    pub kind: Kind,
+}
+
+impl std::str::FromStr for Rule {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let mut words = text.split_whitespace();
+        let symbol = words.next().with_context(|| "Symbol of rule")?;
+        let kind = match words.next().with_context(|| "Arity of rule")? {
+            "separator" => Kind::Separator,
+            arity => {
+                let aggregation = words.next().with_context(|| "Aggregation of rule")?;
+                Kind::Aggregate(arity.parse()?, aggregation.parse()?)
+            }
+        };
+
+        if let Some(what) = words.next() {
+            bail!("Unexpected {what} at the end of rule {text}");
+        }
+
+        let mut chars = symbol.chars();
+        let symbol = match (chars.next(), chars.next()) {
+            (Some(symbol), None) => symbol,
+            _ => bail!("The symbol of a rule must be a single character, not {symbol}"),
+        };
+
+        Ok(Self { symbol, kind })
+    }
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (arity, aggregation) = match self.kind {
+            Kind::Aggregate(arity, aggregation) => (arity, aggregation),
+            Kind::Separator => return write!(f, "{} separator", self.symbol),
+        };
+        let arity = match arity {
+            Arity::Any => "any".to_string(),
+            Arity::Exactly(n) => n.to_string(),
+            Arity::AtLeast(n) => format!(">={n}"),
+            Arity::AtMost(n) => format!("<={n}"),
+            Arity::Between(low, high) => format!("{low}..{high}"),
+        };
+        let aggregation = format!("{aggregation:?}").to_lowercase();
+        write!(f, "{} {} {}", self.symbol, arity, aggregation)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RuleReport {
+    /// How many symbols the rule applied to
+    pub matches: usize,
+    /// The sum of the aggregated values of all matches. For a separator, the sum of the numbers
+    /// which only touch separators of its symbol and so are not parts.
+    pub total: u128,
+}
+
+impl Rule {
+    /// Fails when the value of a symbol or the total overflows
+    pub fn evaluate(&self, schematic: &Schematic) -> Result<RuleReport> {
+        let (arity, aggregation) = match self.kind {
+            Kind::Aggregate(arity, aggregation) => (arity, aggregation),
+            Kind::Separator => return self.separate(schematic),
+        };
+
+        let values = schematic
+            .symbols
+            .iter()
+            .filter(|symbol| symbol.label == self.symbol)
+            .map(|symbol| (symbol, schematic.parts_adjacent_to(symbol.x, symbol.y)))
+            .filter(|(_, parts)| arity.accepts(parts.len()))
+            .map(|(symbol, parts)| {
+                let numbers = parts.iter().map(|part| part.number).collect::<Vec<_>>();
+                aggregation.apply(&numbers).with_context(|| {
+                    format!(
+                        "Rule {self} on {} at ({}, {})",
+                        symbol.label, symbol.x, symbol.y
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(RuleReport {
+            matches: values.len(),
+            total: values
+                .iter()
+                .try_fold(0u128, |total, value| total.checked_add(*value))
+                .with_context(|| format!("The total of rule {self} overflows"))?,
+        })
+    }
+
+    fn separate(&self, schematic: &Schematic) -> Result<RuleReport> {
+        let mut separated = schematic
+            .parts
+            .iter()
+            .filter(|part| !part.symbols.is_empty())
+            .filter(|part| part.symbols.iter().all(|s| s.symbol.label == self.symbol))
+            .map(|part| part.number as u128);
+
+        Ok(RuleReport {
+            matches: schematic
+                .symbols
+                .iter()
+                .filter(|symbol| symbol.label == self.symbol)
+                .count(),
+            total: separated
+                .try_fold(0u128, |total, number| total.checked_add(number))
+                .with_context(|| format!("The total of rule {self} overflows"))?,
+        })
+    }
+}
+
+/// Parses one rule per line, empty lines and lines starting with "//" are ignored
+pub fn parse_rules(text: &str) -> Result<Vec<Rule>> {
+    text.lines()
+        .enumerate()
+        .map(|(idx, line)| (idx, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with("//"))
+        .map(|(idx, line)| {
+            line.parse::<Rule>()
+                .with_context(|| format!("Parsing rule on line {}", idx + 1))
+        })
+        .collect()
+}
@@ -0,0 +1,48 @@
+use crate::card::Card;
+use anyhow::Result;
+use std::collections::VecDeque;
+
+/// Counts scratchcards one card at a time. Only remembers the copies that earlier cards granted
+/// to the cards which have not been seen yet, so memory is proportional to the largest number of
+/// matches on a single card rather than the number of cards.
+#[derive(Debug, Default)]
+pub struct Cascade {
+    /// The copies granted to the next cards, the front is the next card
+    pending: VecDeque<u128>,
+    /// The number of card instances seen so far
+    total: u128,
+}
+
+impl Cascade {
+    /// Records the next card which has @matches matching numbers and returns how many instances
+    /// of it there are
+    pub fn push(&mut self, matches: usize) -> u128 {
+        let instances = 1 + self.pending.pop_front().unwrap_or(0);
+
+        if self.pending.len() < matches {
+            self.pending.resize(matches, 0);
+        }
+
+        for pending in self.pending.iter_mut().take(matches) {
+            *pending += instances;
+        }
+
+        self.total += instances;
+        instances
+    }
+
+    pub fn total(&self) -> u128 {
+        self.total
+    }
+}
+
+/// Counts the instances of all cards, only keeps one card in memory at a time
+pub fn count_instances(cards: impl IntoIterator<Item = Result<Card>>) -> Result<u128> {
+    let mut cascade = Cascade::default();
+
+    for card in cards {
+        cascade.push(card?.matches());
+    }
+
+    Ok(cascade.total())
+}
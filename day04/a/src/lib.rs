@@ -1 +1,3 @@
 pub mod card;
+pub mod cascade;
+pub mod scoring;
@@ -1,26 +1,19 @@
 use std::env::current_dir;
 
+use a::scoring::{score_all, Scheme};
 use anyhow::Result;
-use clap::{arg, command, Parser};
+use clap::Parser;
 
 #[derive(Parser)]
 #[command(about)]
 struct Args {
     #[arg(long, short, default_value = "input/mine")]
     input: std::path::PathBuf,
-}
 
-fn solve(cards: &Vec<a::card::Card>) -> u128 {
-    cards.iter().fold(0u128, |acc, card| {
-        let common = card
-            .mine
-            .iter()
-            .filter(|number| card.winning.contains(*number))
-            .count();
-
-        let score = if common > 0 { 1 << (common - 1) } else { 0 };
-        acc + score
-    })
+    /// How to score each card: doubling, linear, fibonacci, copies, or expr:<expression> where
+    /// the expression may use the number of matches (m) and the card id (id) e.g. "expr:m^2 + 1"
+    #[arg(long, short, default_value = "doubling")]
+    scoring: Scheme,
 }
 
 #[test]
@@ -32,10 +25,32 @@ Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-    let cards = a::card::parse_str(sample)?;
-    let solution = solve(&cards);
+    let score = |scheme: &str| -> Result<u128> {
+        let scheme = scheme.parse::<Scheme>()?;
+        score_all(
+            a::card::read_cards(sample.as_bytes()),
+            scheme.build().as_mut(),
+        )
+    };
+
+    // VV: The cards have 4, 2, 2, 1, 0, 0 matches
+    assert_eq!(score("doubling")?, 13);
+    assert_eq!(score("linear")?, 9);
+    assert_eq!(score("fibonacci")?, 5 + 2 + 2 + 1);
+    assert_eq!(score("copies")?, 30);
+    assert_eq!(score("expr:m^2 + 1")?, 17 + 5 + 5 + 2 + 1 + 1);
+    assert_eq!(
+        score("expr: 2^2 - id * (m - -1) % 4")?,
+        3 + 2 + 3 + 4 + 3 + 2
+    );
+    // VV: Unary minus binds looser than ^
+    assert_eq!(score("expr:-2^2 + 5")?, 6);
 
-    assert_eq!(solution, 13);
+    assert!(score("expr:m - 1").is_err());
+    assert!("expr:m +".parse::<Scheme>().is_err());
+    assert!("expr:(m".parse::<Scheme>().is_err());
+    assert!("expr:2x".parse::<Scheme>().is_err());
+    assert!("halving".parse::<Scheme>().is_err());
 
     Ok(())
 }
@@ -44,8 +59,8 @@ fn main() -> Result<()> {
     let args = Args::parse();
     let path = current_dir()?.join(args.input);
 
-    let cards = a::card::parse_path(&path)?;
-    let solution = solve(&cards);
+    let cards = a::card::read_path(&path)?;
+    let solution = score_all(cards, args.scoring.build().as_mut())?;
 
     println!("{solution}");
     Ok(())
@@ -0,0 +1,294 @@
+use crate::card::Card;
+use crate::cascade::Cascade;
+use anyhow::{bail, Context, Result};
+
+/// Turns the matching numbers of each card into points. Cards are scored in order and a
+/// Scoring may keep state between cards.
+pub trait Scoring {
+    fn score(&mut self, card: &Card, matches: usize) -> Result<u128>;
+}
+
+/// 1 point for the first match, then doubles for every other match (part a)
+pub struct Doubling;
+
+impl Scoring for Doubling {
+    fn score(&mut self, _card: &Card, matches: usize) -> Result<u128> {
+        if matches == 0 {
+            Ok(0)
+        } else {
+            1u128
+                .checked_shl(matches as u32 - 1)
+                .with_context(|| format!("{matches} matches overflow the score"))
+        }
+    }
+}
+
+/// 1 point per match
+pub struct Linear;
+
+impl Scoring for Linear {
+    fn score(&mut self, _card: &Card, matches: usize) -> Result<u128> {
+        Ok(matches as u128)
+    }
+}
+
+/// 0, 1, 2, 3, 5, 8, ... points for 0, 1, 2, 3, 4, 5, ... matches
+pub struct Fibonacci;
+
+impl Scoring for Fibonacci {
+    fn score(&mut self, _card: &Card, matches: usize) -> Result<u128> {
+        if matches == 0 {
+            return Ok(0);
+        }
+
+        let (mut previous, mut current) = (1u128, 1u128);
+        for _ in 1..matches {
+            (previous, current) = (
+                current,
+                current
+                    .checked_add(previous)
+                    .with_context(|| format!("{matches} matches overflow the score"))?,
+            );
+        }
+        Ok(current)
+    }
+}
+
+/// Every match wins a copy of one of the next cards and the score of a card is the number of
+/// its instances (part b)
+#[derive(Default)]
+pub struct CopyGranting(Cascade);
+
+impl Scoring for CopyGranting {
+    fn score(&mut self, _card: &Card, matches: usize) -> Result<u128> {
+        Ok(self.0.push(matches))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+}
+
+/// An arithmetic expression over the variables m (matches) and id (the card id)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Number(i128),
+    Matches,
+    Id,
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, Op, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(&self, card: &Card, matches: usize) -> Result<i128> {
+        Ok(match self {
+            Expr::Number(number) => *number,
+            Expr::Matches => matches as i128,
+            Expr::Id => card.id as i128,
+            Expr::Neg(expr) => expr
+                .eval(card, matches)?
+                .checked_neg()
+                .context("Overflow")?,
+            Expr::Binary(left, op, right) => {
+                let left = left.eval(card, matches)?;
+                let right = right.eval(card, matches)?;
+                match op {
+                    Op::Add => left.checked_add(right),
+                    Op::Sub => left.checked_sub(right),
+                    Op::Mul => left.checked_mul(right),
+                    Op::Div => left.checked_div(right),
+                    Op::Rem => left.checked_rem(right),
+                    Op::Pow => u32::try_from(right)
+                        .ok()
+                        .and_then(|right| left.checked_pow(right)),
+                }
+                .with_context(|| format!("Cannot evaluate {left} {op:?} {right}"))?
+            }
+        })
+    }
+}
+
+impl std::str::FromStr for Expr {
+    type Err = anyhow::Error;
+
+    /// Parses expressions with numbers, the variables m and id, parentheses, unary minus and the
+    /// operators + - * / % ^ with the usual precedence. ^ is right associative.
+    fn from_str(text: &str) -> Result<Self> {
+        struct Parser<'a> {
+            chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+            text: &'a str,
+        }
+
+        impl Parser<'_> {
+            fn peek(&mut self) -> Option<char> {
+                while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+                self.chars.peek().map(|(_, c)| *c)
+            }
+
+            fn binary(
+                &mut self,
+                ops: &[(char, Op)],
+                operand: fn(&mut Self) -> Result<Expr>,
+            ) -> Result<Expr> {
+                let mut left = operand(self)?;
+                while let Some(&(_, op)) = self.peek().and_then(|c| ops.iter().find(|o| o.0 == c)) {
+                    self.chars.next();
+                    left = Expr::Binary(Box::new(left), op, Box::new(operand(self)?));
+                }
+                Ok(left)
+            }
+
+            fn sum(&mut self) -> Result<Expr> {
+                self.binary(&[('+', Op::Add), ('-', Op::Sub)], Self::product)
+            }
+
+            fn product(&mut self) -> Result<Expr> {
+                self.binary(
+                    &[('*', Op::Mul), ('/', Op::Div), ('%', Op::Rem)],
+                    Self::unary,
+                )
+            }
+
+            fn unary(&mut self) -> Result<Expr> {
+                if self.peek() == Some('-') {
+                    self.chars.next();
+                    Ok(Expr::Neg(Box::new(self.unary()?)))
+                } else {
+                    self.power()
+                }
+            }
+
+            fn power(&mut self) -> Result<Expr> {
+                let base = self.atom()?;
+                if self.peek() == Some('^') {
+                    self.chars.next();
+                    Ok(Expr::Binary(
+                        Box::new(base),
+                        Op::Pow,
+                        Box::new(self.unary()?),
+                    ))
+                } else {
+                    Ok(base)
+                }
+            }
+
+            fn atom(&mut self) -> Result<Expr> {
+                let Some(c) = self.peek() else {
+                    bail!("Unexpected end of expression {}", self.text);
+                };
+                let (start, _) = *self.chars.peek().unwrap();
+
+                if c == '(' {
+                    self.chars.next();
+                    let expr = self.sum()?;
+                    if self.peek() != Some(')') {
+                        bail!("Missing ')' in expression {}", self.text);
+                    }
+                    self.chars.next();
+                    return Ok(expr);
+                }
+
+                let mut end = start;
+                while let Some((idx, c)) = self.chars.next_if(|(_, c)| c.is_alphanumeric()) {
+                    end = idx + c.len_utf8();
+                }
+
+                match &self.text[start..end] {
+                    "" => bail!("Unexpected {c:?} at {start} in expression {}", self.text),
+                    "m" => Ok(Expr::Matches),
+                    "id" => Ok(Expr::Id),
+                    number => Ok(Expr::Number(number.parse().with_context(|| {
+                        format!("Unknown term {number} in expression {}", self.text)
+                    })?)),
+                }
+            }
+        }
+
+        let mut parser = Parser {
+            chars: text.char_indices().peekable(),
+            text,
+        };
+        let expr = parser.sum()?;
+
+        if let Some(c) = parser.peek() {
+            bail!("Unexpected {c:?} in expression {text}");
+        }
+
+        Ok(expr)
+    }
+}
+
+/// Scores cards with a custom Expr
+pub struct Expression(pub Expr);
+
+impl Scoring for Expression {
+    fn score(&mut self, card: &Card, matches: usize) -> Result<u128> {
+        let score = self.0.eval(card, matches)?;
+        u128::try_from(score)
+            .with_context(|| format!("Card {} has a negative score {score}", card.id))
+    }
+}
+
+/// The built-in scoring schemes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scheme {
+    Doubling,
+    Linear,
+    Fibonacci,
+    CopyGranting,
+    Expression(Expr),
+}
+
+impl Scheme {
+    pub fn build(&self) -> Box<dyn Scoring> {
+        match self {
+            Scheme::Doubling => Box::new(Doubling),
+            Scheme::Linear => Box::new(Linear),
+            Scheme::Fibonacci => Box::new(Fibonacci),
+            Scheme::CopyGranting => Box::new(CopyGranting::default()),
+            Scheme::Expression(expr) => Box::new(Expression(expr.clone())),
+        }
+    }
+}
+
+impl std::str::FromStr for Scheme {
+    type Err = anyhow::Error;
+
+    /// One of doubling, linear, fibonacci, copies, or expr:<expression> e.g. "expr:m * m + 1"
+    fn from_str(text: &str) -> Result<Self> {
+        Ok(match text {
+            "doubling" => Scheme::Doubling,
+            "linear" => Scheme::Linear,
+            "fibonacci" => Scheme::Fibonacci,
+            "copies" => Scheme::CopyGranting,
+            text => match text.strip_prefix("expr:") {
+                Some(expr) => Scheme::Expression(expr.parse()?),
+                None => bail!("Unknown scoring scheme {text}"),
+            },
+        })
+    }
+}
+
+/// The sum of the scores of all cards, only keeps one card in memory at a time
+pub fn score_all(
+    cards: impl IntoIterator<Item = Result<Card>>,
+    scoring: &mut dyn Scoring,
+) -> Result<u128> {
+    let mut total = 0u128;
+
+    for card in cards {
+        let card = card?;
+        let score = scoring.score(&card, card.matches())?;
+        total = total
+            .checked_add(score)
+            .with_context(|| format!("The total score overflows at Card {}", card.id))?;
+    }
+
+    Ok(total)
+}
@@ -1,18 +1,31 @@
 use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
 use std::io::{BufRead, BufReader};
 
 #[derive(Debug)]
 pub struct Card {
     /// The Card ID
     pub id: u32,
-    /// The winning numbers
-    pub winning: Vec<u32>,
+    /// The winning numbers, a set so that cards with many numbers are quick to check
+    pub winning: HashSet<u32>,
     /// Numbers on the card
     pub mine: Vec<u32>,
 }
 
 impl Card {
-    pub fn from_str(text: &str) -> Result<Self> {
+    /// How many of my numbers are winning numbers
+    pub fn matches(&self) -> usize {
+        self.mine
+            .iter()
+            .filter(|number| self.winning.contains(*number))
+            .count()
+    }
+}
+
+impl std::str::FromStr for Card {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
         let text = text.trim();
         if !text.starts_with("Card ") {
             bail!("text does not start with \"Card \"")
@@ -30,40 +43,45 @@ impl Card {
             .with_context(|| "Parsing the Card id")?;
 
         let (winning, mine) = rest
-            .split_once("|")
+            .split_once('|')
             .with_context(|| "Extracting the winning and mine numbers")?;
 
-        fn extract_numbers(text: &str) -> Vec<u32> {
-            text.trim()
-                .split(' ')
-                .filter(|word| word.len() > 0)
-                .map(|x| x.trim().parse::<u32>().expect("Parsing winning cards"))
+        fn extract_numbers<T: FromIterator<u32>>(text: &str) -> Result<T> {
+            text.split_whitespace()
+                .map(|x| {
+                    x.parse::<u32>()
+                        .with_context(|| format!("Parsing number {x}"))
+                })
                 .collect()
         }
 
-        let winning = extract_numbers(winning);
-        let mine = extract_numbers(mine);
+        let winning = extract_numbers(winning)
+            .with_context(|| format!("Parsing the winning numbers of Card {id}"))?;
+        let mine =
+            extract_numbers(mine).with_context(|| format!("Parsing the numbers of Card {id}"))?;
 
         Ok(Self { id, winning, mine })
     }
 }
 
-pub fn parse_str(text: &str) -> Result<Vec<Card>> {
-    Ok(text
+/// Lazily parses one Card per non-empty line of @reader
+pub fn read_cards(reader: impl BufRead) -> impl Iterator<Item = Result<Card>> {
+    reader
         .lines()
-        .map(|line| {
-            println!("Line is {line:?}");
-            Card::from_str(line).unwrap()
-        })
-        .collect())
+        .filter(|line| !matches!(line, Ok(line) if line.trim().is_empty()))
+        .map(|line| line?.parse::<Card>())
 }
 
-pub fn parse_path(path: &std::path::Path) -> Result<Vec<Card>> {
+/// Lazily parses the Cards in the file at @path
+pub fn read_path(path: &std::path::Path) -> Result<impl Iterator<Item = Result<Card>>> {
     let file = std::fs::File::open(path).with_context(|| "Unable to open file")?;
-    let mut reader = BufReader::new(file);
+    Ok(read_cards(BufReader::new(file)))
+}
 
-    Ok(reader
-        .lines()
-        .map(|line| Card::from_str(&line.unwrap()).unwrap())
-        .collect())
+pub fn parse_str(text: &str) -> Result<Vec<Card>> {
+    read_cards(text.as_bytes()).collect()
+}
+
+pub fn parse_path(path: &std::path::Path) -> Result<Vec<Card>> {
+    read_path(path)?.collect()
 }
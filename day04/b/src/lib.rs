@@ -3,3 +3,8 @@
 // #[path = "../../a/src/card.rs"]
 
 pub mod card;
+#[path = "../../a/src/cascade.rs"]
+pub mod cascade;
+#[path = "../../a/src/scoring.rs"]
+pub mod scoring;
+pub mod trace;
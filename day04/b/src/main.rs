@@ -1,37 +1,82 @@
 use std::env::current_dir;
 
 use anyhow::Result;
-use clap::{arg, command, Parser};
+use b::scoring::{score_all, Scheme};
+use b::trace::{trace, write_csv, write_dot};
+use clap::{Parser, ValueEnum};
 
 #[derive(Parser)]
 #[command(about)]
 struct Args {
     #[arg(long, short, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    /// How to score each card: doubling, linear, fibonacci, copies, or expr:<expression> where
+    /// the expression may use the number of matches (m) and the card id (id) e.g. "expr:m^2 + 1"
+    #[arg(long, short, default_value = "copies")]
+    scoring: Scheme,
+
+    /// Print where the copies of every card came from instead of the total number of cards
+    #[arg(long, short, value_enum)]
+    trace: Option<TraceFormat>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum TraceFormat {
+    Csv,
+    Json,
+    Dot,
+}
+
+#[test]
+fn test_sample() -> Result<()> {
+    let sample = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
+
+    let solution = b::cascade::count_instances(b::card::read_cards(sample.as_bytes()))?;
+    assert_eq!(solution, 30);
+
+    let mut scoring = "copies".parse::<Scheme>()?.build();
+    let solution = score_all(b::card::read_cards(sample.as_bytes()), scoring.as_mut())?;
+    assert_eq!(solution, 30);
+
+    Ok(())
 }
 
-fn solve(cards: &Vec<b::card::Card>) -> u128 {
-    fn calc_common(card: &b::card::Card) -> usize {
-        card.mine
-            .iter()
-            .filter(|number| card.winning.contains(*number))
-            .count()
+#[test]
+fn test_streaming() -> Result<()> {
+    // VV: Every card matches its 3 numbers so the ring buffer never needs more than 3 slots and
+    // the number of instances grows like the tribonacci numbers
+    let cards = (1..=60)
+        .map(|id| format!("Card {id}: 1 2 3 | 1 2 3"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut cascade = b::cascade::Cascade::default();
+    let mut expected = vec![1u128];
+    let mut instances = vec![];
+
+    for card in b::card::read_cards(cards.as_bytes()) {
+        instances.push(cascade.push(card?.matches()));
     }
 
-    let mut total_cards = vec![1; cards.len()];
+    for idx in 1..60usize {
+        let granted = expected[idx.saturating_sub(3)..idx].iter().sum::<u128>();
+        expected.push(1 + granted);
+    }
 
-    cards.iter().enumerate().fold(0u128, |acc, (idx, card)| {
-        let common = calc_common(card);
-        for i in idx + 1..(idx + 1 + common) {
-            total_cards[i] += total_cards[idx];
-        }
+    assert_eq!(instances, expected);
+    assert_eq!(cascade.total(), expected.iter().sum::<u128>());
 
-        acc + total_cards[idx]
-    })
+    Ok(())
 }
 
 #[test]
-fn test_sample() -> Result<()> {
+fn test_trace() -> Result<()> {
     let sample = "Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
 Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
 Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
@@ -39,10 +84,32 @@ Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
 Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
 Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11";
 
-    let cards = b::card::parse_str(sample)?;
-    let solution = solve(&cards);
+    let traces = trace(b::card::read_cards(sample.as_bytes()))?;
 
-    assert_eq!(solution, 30);
+    let instances = traces.iter().map(|t| t.instances).collect::<Vec<_>>();
+    assert_eq!(instances, [1, 2, 4, 8, 14, 1]);
+
+    let granted = traces.iter().map(|t| t.granted).collect::<Vec<_>>();
+    assert_eq!(granted, [4, 4, 8, 8, 0, 0]);
+
+    assert_eq!(
+        traces[4].granted_by,
+        [
+            b::trace::Grant { from: 1, copies: 1 },
+            b::trace::Grant { from: 3, copies: 4 },
+            b::trace::Grant { from: 4, copies: 8 }
+        ]
+    );
+
+    let mut csv = vec![];
+    write_csv(&mut csv, &traces)?;
+    let csv = String::from_utf8(csv)?;
+    assert_eq!(csv.lines().nth(5), Some("5,0,14,0,1:1;3:4;4:8"));
+
+    let mut dot = vec![];
+    write_dot(&mut dot, &traces)?;
+    let dot = String::from_utf8(dot)?;
+    assert!(dot.contains("  card4 -> card5 [label=\"8\"];"));
 
     Ok(())
 }
@@ -51,9 +118,17 @@ fn main() -> Result<()> {
     let args = Args::parse();
     let path = current_dir()?.join(args.input);
 
-    let cards = b::card::parse_path(&path)?;
-    let solution = solve(&cards);
+    let cards = b::card::read_path(&path)?;
 
-    println!("{solution}");
+    let out = &mut std::io::stdout();
+    match args.trace {
+        None => {
+            let solution = score_all(cards, args.scoring.build().as_mut())?;
+            println!("{solution}");
+        }
+        Some(TraceFormat::Csv) => write_csv(out, &trace(cards)?)?,
+        Some(TraceFormat::Json) => println!("{}", serde_json::to_string_pretty(&trace(cards)?)?),
+        Some(TraceFormat::Dot) => write_dot(out, &trace(cards)?)?,
+    }
     Ok(())
 }
@@ -0,0 +1,117 @@
+use crate::card::Card;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Grant {
+    /// The id of the card which granted the copies
+    pub from: u32,
+    pub copies: u128,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CardTrace {
+    pub id: u32,
+    pub matches: usize,
+    /// The original card plus all the copies it won
+    pub instances: u128,
+    /// The cards which granted copies of this card
+    pub granted_by: Vec<Grant>,
+    /// How many copies of later cards this card granted in total
+    pub granted: u128,
+}
+
+/// Follows the scratchcard cascade and records where every copy came from. Unlike
+/// Cascade this keeps a record for every card.
+pub fn trace(cards: impl IntoIterator<Item = Result<Card>>) -> Result<Vec<CardTrace>> {
+    let mut traces: Vec<CardTrace> = vec![];
+    // VV: The grants to the next cards along with the index of the granting card in @traces
+    let mut pending: VecDeque<Vec<(usize, u128)>> = VecDeque::new();
+
+    for card in cards {
+        let card = card?;
+        let matches = card.matches();
+        let grants = pending.pop_front().unwrap_or_default();
+        let instances = 1 + grants.iter().map(|(_, copies)| copies).sum::<u128>();
+
+        let granted_by = grants
+            .into_iter()
+            .map(|(from, copies)| {
+                traces[from].granted += copies;
+                Grant {
+                    from: traces[from].id,
+                    copies,
+                }
+            })
+            .collect();
+
+        if pending.len() < matches {
+            pending.resize(matches, vec![]);
+        }
+        for grants in pending.iter_mut().take(matches) {
+            grants.push((traces.len(), instances));
+        }
+
+        traces.push(CardTrace {
+            id: card.id,
+            matches,
+            instances,
+            granted_by,
+            granted: 0,
+        });
+    }
+
+    Ok(traces)
+}
+
+/// One row per card, the granted_by column lists "<id>:<copies>" pairs separated by ';'
+pub fn write_csv(out: &mut impl Write, traces: &[CardTrace]) -> Result<()> {
+    writeln!(out, "id,matches,instances,granted,granted_by")?;
+
+    for trace in traces {
+        let granted_by = trace
+            .granted_by
+            .iter()
+            .map(|grant| format!("{}:{}", grant.from, grant.copies))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        writeln!(
+            out,
+            "{},{},{},{},{}",
+            trace.id, trace.matches, trace.instances, trace.granted, granted_by
+        )?;
+    }
+
+    Ok(())
+}
+
+/// A graph with a node per card and an edge for every grant, labelled with the number of copies
+pub fn write_dot(out: &mut impl Write, traces: &[CardTrace]) -> Result<()> {
+    writeln!(out, "digraph cascade {{")?;
+    writeln!(out, "  rankdir=LR;")?;
+    writeln!(out, "  node [shape=box];")?;
+
+    for trace in traces {
+        writeln!(
+            out,
+            "  card{} [label=\"Card {}\\n{} instances\\ngranted {}\"];",
+            trace.id, trace.id, trace.instances, trace.granted
+        )?;
+    }
+
+    for trace in traces {
+        for grant in &trace.granted_by {
+            writeln!(
+                out,
+                "  card{} -> card{} [label=\"{}\"];",
+                grant.from, trace.id, grant.copies
+            )?;
+        }
+    }
+
+    writeln!(out, "}}")?;
+    Ok(())
+}
@@ -1 +1,2 @@
 pub mod logistics;
+pub mod range_map;
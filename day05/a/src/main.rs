@@ -1,37 +1,50 @@
 use a::logistics::Book;
+use a::range_map::{intersect_ranges, merge_ranges, parse_range, RangeMap};
 use anyhow::{Context, Result};
-use clap::{arg, command, Parser};
+use clap::Parser;
+use std::ops::Range;
 
 #[derive(Parser)]
 #[command(version, about)]
 struct Args {
     #[arg(long, short, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    /// The category of the values on the seeds: line
+    #[arg(long, short, default_value = "seed")]
+    from: String,
+
+    /// The category to convert them to
+    #[arg(long, short, default_value = "location")]
+    to: String,
+
+    /// Instead of solving, print the seeds which end up in the --to values START..END (END is
+    /// excluded) or at the single value N
+    #[arg(long, short, value_parser = parse_range)]
+    locations: Option<Range<usize>>,
+
+    /// Fail instead of warning about zero length Mappers and overlapping source ranges
+    #[arg(long)]
+    strict: bool,
 }
 
-fn solve(book: &Book) -> usize {
+fn solve(book: &Book, pipeline: &RangeMap) -> Result<usize> {
     book.seeds
         .iter()
-        .map(|seed| {
-            // println!("Seed: {seed}");
-            let last = book.rules.iter().fold(*seed, |src, rule| {
-                let n = rule.iter().fold((src, None), |init, mapper| {
-                    if init.1.is_none() {
-                        (init.0, mapper.src_to_dest(init.0))
-                    } else {
-                        init
-                    }
-                });
-                // println!(" {n:?}");
-                n.1.or(Some(src)).unwrap()
-            });
-
-            println!("{seed} -> {last}");
-            last
-        })
+        .map(|seed| pipeline.get(*seed))
         .min()
-        .with_context(|| "No rules")
-        .unwrap()
+        .with_context(|| "No seeds")
+}
+
+/// The seeds which end up in @locations
+fn seeds_reaching(book: &Book, pipeline: &RangeMap, locations: Range<usize>) -> Vec<usize> {
+    let seeds = pipeline.preimage(locations);
+    let seeds = intersect_ranges(
+        &seeds,
+        &merge_ranges(book.seeds.iter().map(|seed| *seed..seed + 1).collect()),
+    );
+
+    seeds.into_iter().flatten().collect()
 }
 
 #[test]
@@ -70,11 +83,244 @@ humidity-to-location map:
 60 56 37
 56 93 4";
 
-    let book = a::logistics::Book::parse_text(sample)?;
-    let solution = solve(&book);
-
+    let book = Book::parse_text(sample)?;
+    let pipeline = book.pipeline("seed", "location")?;
+    let solution = solve(&book, &pipeline)?;
     assert_eq!(solution, 35);
 
+    // VV: The precomputed pipeline agrees with applying the rules one Mapper at a time
+    for seed in 0..200 {
+        let location = book.maps.iter().fold(seed, |value, map| {
+            map.rule
+                .iter()
+                .find_map(|mapper| mapper.src_to_dest(value))
+                .unwrap_or(value)
+        });
+        assert_eq!(pipeline.get(seed), location);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_preimage() -> Result<()> {
+    let sample = "seeds: 79 14 55 13 56
+
+seed-to-soil map:
+50 98 2
+52 50 48
+
+soil-to-fertilizer map:
+0 15 37
+37 52 2
+39 0 15
+
+fertilizer-to-water map:
+49 53 8
+0 11 42
+42 0 7
+57 7 4
+
+water-to-light map:
+88 18 7
+18 25 70
+
+light-to-temperature map:
+45 77 23
+81 45 19
+68 64 13
+
+temperature-to-humidity map:
+0 69 1
+1 0 69
+
+humidity-to-location map:
+60 56 37
+56 93 4";
+
+    let book = Book::parse_text(sample)?;
+    let pipeline = book.pipeline("seed", "location")?;
+
+    // VV: The preimage of a range of locations holds exactly the seeds which end up there
+    let locate = |seed| {
+        book.maps.iter().fold(seed, |value, map| {
+            map.rule
+                .iter()
+                .find_map(|mapper| mapper.src_to_dest(value))
+                .unwrap_or(value)
+        })
+    };
+    for locations in [0..10, 35..36, 40..60, 82..83, 90..200] {
+        let seeds = pipeline.preimage(locations.clone());
+        for seed in 0..200 {
+            let expected = locations.contains(&locate(seed));
+            assert_eq!(seeds.iter().any(|seeds| seeds.contains(&seed)), expected);
+        }
+
+        for map in &book.maps {
+            let layer = RangeMap::from_rule(&map.rule);
+            for value in layer.preimage(locations.clone()).into_iter().flatten() {
+                assert!(locations.contains(&layer.get(value)));
+            }
+        }
+    }
+    assert_eq!(seeds_reaching(&book, &pipeline, 35..36), [13]);
+    assert_eq!(seeds_reaching(&book, &pipeline, 80..90), [55, 56, 79]);
+
+    Ok(())
+}
+
+#[test]
+fn test_range_map() -> Result<()> {
+    use a::logistics::Mapper;
+    use a::range_map::Segment;
+
+    let segment = |start, end, dest| Segment { start, end, dest };
+
+    // VV: The second Mapper overlaps the first one, which wins in [12, 15)
+    let map = RangeMap::from_rule(&[
+        Mapper {
+            dest: 50,
+            src: 10,
+            len: 5,
+        },
+        Mapper {
+            dest: 100,
+            src: 12,
+            len: 8,
+        },
+    ]);
+    assert_eq!(
+        map.segments(),
+        [
+            segment(0, 10, 0),
+            segment(10, 15, 50),
+            segment(15, 20, 103),
+            segment(20, usize::MAX, 20),
+        ]
+    );
+    assert_eq!(map.apply_range(8..17), [8..10, 50..55, 103..105]);
+    assert_eq!(map.apply_ranges([0..5, 3..12]), [0..10, 50..52]);
+
+    assert_eq!(map.then(&RangeMap::identity()), map);
+    assert_eq!(RangeMap::identity().then(&map), map);
+
+    // VV: Swaps [10, 15) and [50, 55) so it undoes part of the first map
+    let swap = RangeMap::from_rule(&[
+        Mapper {
+            dest: 10,
+            src: 50,
+            len: 5,
+        },
+        Mapper {
+            dest: 50,
+            src: 10,
+            len: 5,
+        },
+    ]);
+    let composed = map.then(&swap);
+    assert_eq!(
+        &composed.segments()[..2],
+        [segment(0, 15, 0), segment(15, 20, 103)]
+    );
+    for value in 0..200 {
+        assert_eq!(composed.get(value), swap.get(map.get(value)));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_almanac_graph() -> Result<()> {
+    // VV: Sections out of order, a branch (soil-to-water), and a second chain (seed-to-colour)
+    let almanac = "seeds: 1 5 10
+
+water-to-location map:
+100 0 60
+
+seed-to-soil map:
+10 0 10
+
+soil-to-fertilizer map:
+0 10 10
+
+fertilizer-to-water map:
+50 0 10
+
+soil-to-water map:
+30 10 5
+
+seed-to-colour map:
+7 5 1";
+
+    let book = Book::parse_text(almanac)?;
+    let names = |chain: Vec<&a::logistics::Map>| {
+        chain
+            .iter()
+            .map(|map| format!("{}-{}", map.from, map.to))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        names(book.chain("seed", "location")?),
+        ["seed-soil", "soil-water", "water-location"]
+    );
+    assert_eq!(names(book.chain("soil", "water")?), ["soil-water"]);
+    assert!(book.chain("seed", "seed")?.is_empty());
+    assert_eq!(book.pipeline("seed", "colour")?.get(5), 7);
+    assert_eq!(book.pipeline("seed", "location")?.get(1), 131);
+    assert_eq!(book.pipeline("fertilizer", "location")?.get(1), 151);
+
+    assert!(book.chain("location", "seed").is_err());
+    assert!(book.chain("seed", "humidity").is_err());
+
+    let error = |almanac: &str| match Book::parse_text(almanac) {
+        Ok(_) => String::new(),
+        Err(error) => format!("{error:#}"),
+    };
+
+    assert!(error("seed-to-soil map:\n1 2 3").contains("seeds:"));
+    assert_eq!(error(""), "No seeds: line");
+    // VV: Zero length Mappers are dropped and overlapping ones kept, both with a warning
+    let book = Book::parse_text("seeds: 1\nseed-to-soil map:\n1 2 0\n1 2 3\n10 4 2")?;
+    assert_eq!(
+        book.warnings,
+        [
+            "Line 3: Mapper 1 2 0 has zero length",
+            "The seed-to-soil map has overlapping source ranges: 1 2 3 and 10 4 2"
+        ]
+    );
+    assert_eq!(book.maps[0].rule.len(), 2);
+    assert_eq!(book.pipeline("seed", "soil")?.get(4), 3);
+    assert!(book.check(false).is_ok());
+    assert_eq!(
+        format!("{:#}", book.check(true).unwrap_err()),
+        book.warnings.join("\n")
+    );
+    // VV: [0, 100) overlaps [30, 40) even though [10, 20) sorts between them
+    let book = Book::parse_text("seeds: 1\nseed-to-soil map:\n5 0 100\n7 10 10\n9 30 10")?;
+    assert_eq!(
+        book.warnings,
+        [
+            "The seed-to-soil map has overlapping source ranges: 5 0 100 and 7 10 10",
+            "The seed-to-soil map has overlapping source ranges: 5 0 100 and 9 30 10"
+        ]
+    );
+    assert!(book.check(true).is_err());
+    assert!(Book::parse_text("seeds: 1\nseed-to-soil map:\n1 2 3")?
+        .check(true)
+        .is_ok());
+    assert_eq!(
+        error("seeds: 1\nseed-to-soil map:\nsoil-to-seed map:\nseed-to-soil map:"),
+        "Line 4: Duplicate seed-to-soil map"
+    );
+    assert_eq!(
+        error("seeds: 1\n1 2 3"),
+        "Line 2: Mapper 1 2 3 is not in a map"
+    );
+    assert!(error("seeds: 1\nseed soil map:").contains("Line 2"));
+    assert!(error("seeds: 1 x").contains("Parsing seed x"));
+
     Ok(())
 }
 
@@ -82,9 +328,18 @@ fn main() -> Result<()> {
     let args = Args::parse();
     let path = std::env::current_dir().unwrap().join(args.input);
 
-    let book = a::logistics::Book::parse_path(&path)?;
-    let solution = solve(&book);
+    let book = Book::parse_path(&path)?;
+    book.check(args.strict)?;
+    let pipeline = book.pipeline(&args.from, &args.to)?;
+
+    if let Some(locations) = args.locations {
+        for seed in seeds_reaching(&book, &pipeline, locations) {
+            println!("{seed}");
+        }
+        return Ok(());
+    }
 
+    let solution = solve(&book, &pipeline)?;
     println!("{solution}");
 
     Ok(())
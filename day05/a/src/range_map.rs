@@ -0,0 +1,242 @@
+use crate::logistics::{Mapper, Rule};
+use anyhow::{Context, Result};
+use std::ops::Range;
+
+/// Maps the values in [start, end) to [dest, dest + end - start)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Segment {
+    pub start: usize,
+    pub end: usize,
+    pub dest: usize,
+}
+
+impl Segment {
+    /// Where @value goes, @value must be in [start, end)
+    pub fn get(&self, value: usize) -> usize {
+        self.dest.saturating_add(value - self.start)
+    }
+
+    /// The values that [start, end) maps to
+    pub fn image(&self) -> Range<usize> {
+        self.dest..self.get(self.end)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.start == self.dest
+    }
+}
+
+/// A piecewise map over [0, usize::MAX) made of sorted, non-overlapping, contiguous Segments.
+/// Values which no Mapper covers map to themselves and get their own identity Segments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeMap {
+    segments: Vec<Segment>,
+}
+
+impl Default for RangeMap {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl RangeMap {
+    pub fn identity() -> Self {
+        Self {
+            segments: vec![Segment {
+                start: 0,
+                end: usize::MAX,
+                dest: 0,
+            }],
+        }
+    }
+
+    /// Drops empty Segments and joins neighbours which continue each other
+    fn from_segments(segments: impl IntoIterator<Item = Segment>) -> Self {
+        let mut joined: Vec<Segment> = vec![];
+
+        for segment in segments.into_iter().filter(|s| s.start < s.end) {
+            match joined.last_mut() {
+                Some(last) if last.end == segment.start && last.get(last.end) == segment.dest => {
+                    last.end = segment.end;
+                }
+                _ => joined.push(segment),
+            }
+        }
+
+        Self { segments: joined }
+    }
+
+    /// When Mappers overlap the one which comes first in the @rule wins, just like when applying
+    /// the Mappers of the rule one after the other
+    pub fn from_rule(rule: &[Mapper]) -> Self {
+        let mut bounds = vec![0, usize::MAX];
+        for mapper in rule {
+            bounds.push(mapper.src);
+            bounds.push(mapper.src.saturating_add(mapper.len));
+        }
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        Self::from_segments(bounds.windows(2).map(|bounds| {
+            let (start, end) = (bounds[0], bounds[1]);
+            let dest = rule
+                .iter()
+                .find_map(|mapper| mapper.src_to_dest(start))
+                .unwrap_or(start);
+            Segment { start, end, dest }
+        }))
+    }
+
+    /// Applies the @rules one after the other
+    pub fn pipeline<'a>(rules: impl IntoIterator<Item = &'a Rule>) -> Self {
+        rules.into_iter().fold(Self::identity(), |map, rule| {
+            map.then(&Self::from_rule(rule))
+        })
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
+    /// The segments which overlap @range, in order
+    fn overlapping(&self, range: Range<usize>) -> impl Iterator<Item = &Segment> {
+        let first = self.segments.partition_point(|s| s.end <= range.start);
+        self.segments[first..]
+            .iter()
+            .take_while(move |s| s.start < range.end)
+    }
+
+    pub fn get(&self, value: usize) -> usize {
+        let idx = self.segments.partition_point(|s| s.end <= value);
+        self.segments.get(idx).map_or(value, |s| s.get(value))
+    }
+
+    /// Where the values in @range go, one contiguous range per Segment that @range overlaps
+    pub fn apply_range(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        if range.is_empty() {
+            return vec![];
+        }
+
+        self.overlapping(range.clone())
+            .map(|s| s.get(range.start.max(s.start))..s.get(range.end.min(s.end)))
+            .filter(|image| !image.is_empty())
+            .collect()
+    }
+
+    /// Where the values in @ranges go, sorted and with overlapping ranges merged
+    pub fn apply_ranges(
+        &self,
+        ranges: impl IntoIterator<Item = Range<usize>>,
+    ) -> Vec<Range<usize>> {
+        merge_ranges(
+            ranges
+                .into_iter()
+                .flat_map(|range| self.apply_range(range))
+                .collect(),
+        )
+    }
+
+    /// The values which map into @range, sorted and merged. A RangeMap need not be one-to-one so
+    /// the values of several Segments may reach the same @range.
+    pub fn preimage(&self, range: Range<usize>) -> Vec<Range<usize>> {
+        merge_ranges(
+            self.segments
+                .iter()
+                .filter_map(|segment| {
+                    let image = segment.image();
+                    let from = image.start.max(range.start);
+                    let to = image.end.min(range.end);
+                    (from < to).then(|| {
+                        segment.start + (from - image.start)..segment.start + (to - image.start)
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// The values which map into any of @ranges, sorted and merged
+    pub fn preimages(&self, ranges: impl IntoIterator<Item = Range<usize>>) -> Vec<Range<usize>> {
+        merge_ranges(
+            ranges
+                .into_iter()
+                .flat_map(|range| self.preimage(range))
+                .collect(),
+        )
+    }
+
+    /// The map which applies self and then @next
+    pub fn then(&self, next: &RangeMap) -> RangeMap {
+        let mut segments = vec![];
+
+        for segment in &self.segments {
+            let image = segment.image();
+
+            for piece in next.overlapping(image.clone()) {
+                let from = image.start.max(piece.start);
+                let to = image.end.min(piece.end);
+                segments.push(Segment {
+                    start: segment.start + (from - image.start),
+                    end: segment.start + (to - image.start),
+                    dest: piece.get(from),
+                });
+            }
+        }
+
+        Self::from_segments(segments)
+    }
+}
+
+/// Sorts @ranges and merges the ones which overlap or touch
+pub fn merge_ranges(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| range.start);
+
+    let mut merged: Vec<Range<usize>> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    merged
+}
+
+/// The values which are in both @left and @right, both must be sorted and merged
+pub fn intersect_ranges(left: &[Range<usize>], right: &[Range<usize>]) -> Vec<Range<usize>> {
+    let mut both = vec![];
+    let (mut l, mut r) = (0, 0);
+
+    while l < left.len() && r < right.len() {
+        let from = left[l].start.max(right[r].start);
+        let to = left[l].end.min(right[r].end);
+        if from < to {
+            both.push(from..to);
+        }
+
+        if left[l].end < right[r].end {
+            l += 1;
+        } else {
+            r += 1;
+        }
+    }
+
+    both
+}
+
+/// Parses "START..END" (END is excluded) or a single value
+pub fn parse_range(text: &str) -> Result<Range<usize>> {
+    let number = |text: &str| {
+        text.trim()
+            .parse::<usize>()
+            .with_context(|| format!("Invalid range bound {text}"))
+    };
+
+    match text.split_once("..") {
+        Some((start, end)) => Ok(number(start)?..number(end)?),
+        None => {
+            let value = number(text)?;
+            Ok(value..value + 1)
+        }
+    }
+}
@@ -1 +1,3 @@
 pub mod logistics;
+#[path = "../../a/src/range_map.rs"]
+pub mod range_map;
@@ -1,4 +1,6 @@
+use crate::range_map::RangeMap;
 use anyhow::{bail, Context, Result};
+use std::collections::{HashMap, VecDeque};
 use std::io::BufRead;
 
 /// Maps a value from range [src, src+len) to range [dest, dest+len)
@@ -7,40 +9,48 @@ pub struct Mapper {
     pub dest: usize,
     pub src: usize,
     pub len: usize,
-    // pub accessed: bool,
 }
 
 pub type Rule = Vec<Mapper>;
 
+/// The Rule of a "<from>-to-<to> map:" section
+#[derive(Debug, Clone)]
+pub struct Map {
+    pub from: String,
+    pub to: String,
+    pub rule: Rule,
+}
+
 #[derive(Debug)]
 pub struct Book {
+    /// The (start, length) of each range of seeds, sorted by start
     pub seeds: Vec<(usize, usize)>,
-    pub rules: Vec<Rule>,
+    /// In the order they appear in the almanac
+    pub maps: Vec<Map>,
+    /// Problems which do not stop the almanac from being used, e.g. zero length Mappers
+    pub warnings: Vec<String>,
 }
 
 impl Mapper {
     fn from_str(s: &str) -> Result<Self> {
         let s = s.trim();
-        let numbers: Vec<usize> = s
-            .split(' ')
-            .filter(|x| x.len() > 0)
+        let numbers = s
+            .split_whitespace()
             .map(|x| {
                 x.parse::<usize>()
                     .with_context(|| format!("Parsing Mapper from {s}"))
-                    .unwrap()
             })
-            .collect();
+            .collect::<Result<Vec<_>>>()?;
 
-        if numbers.len() != 3 {
+        let &[dest, src, len] = numbers.as_slice() else {
             bail!("Line {s} does not contain 3 numbers - cannot build a Mapper out of it");
+        };
+
+        if src.checked_add(len).is_none() || dest.checked_add(len).is_none() {
+            bail!("Mapper {s} goes past {}", usize::MAX);
         }
 
-        Ok(Self {
-            dest: *numbers.get(0).unwrap(),
-            src: *numbers.get(1).unwrap(),
-            len: *numbers.get(2).unwrap(),
-            // accessed: false,
-        })
+        Ok(Self { dest, src, len })
     }
 
     pub fn src_to_dest(&self, src: usize) -> Option<usize> {
@@ -52,82 +62,121 @@ impl Mapper {
     }
 }
 
-impl Book {
-    pub fn parse_reader(reader: Box<dyn BufRead>) -> Result<Self> {
-        let mut reader = reader.lines();
+impl Map {
+    /// Parses "<from>-to-<to> map:"
+    fn from_header(line: &str) -> Result<Self> {
+        let (from, to) = line
+            .strip_suffix("map:")
+            .and_then(|names| names.trim().split_once("-to-"))
+            .with_context(|| format!("{line} is not a \"<from>-to-<to> map:\" header"))?;
 
-        let mut rules = vec![];
-        let mut seeds: Option<Vec<usize>> = None;
-
-        // VV: First process the line starting with "seeds: "
-        for line in reader.by_ref() {
-            let line = line?;
-            let line = line.trim();
+        if from.is_empty() || to.is_empty() {
+            bail!("Missing category name in {line}");
+        }
 
-            if line.len() == 0 {
-                continue;
-            }
+        Ok(Self {
+            from: from.to_string(),
+            to: to.to_string(),
+            rule: vec![],
+        })
+    }
 
-            if !line.starts_with("seeds:") {
-                bail!("First line was supposed to start with \"seeds:\" but found {line} instead");
+    /// A warning for each pair of Mappers whose source ranges overlap. The first of them in the
+    /// almanac wins where they do.
+    fn overlaps(&self) -> Vec<String> {
+        let mut mappers = self.rule.iter().collect::<Vec<_>>();
+        mappers.sort_by_key(|mapper| mapper.src);
+
+        let mut warnings = vec![];
+        for (idx, later) in mappers.iter().enumerate() {
+            // VV: Any earlier Mapper may reach past @later, not only its neighbour
+            for earlier in mappers[..idx]
+                .iter()
+                .filter(|earlier| earlier.src + earlier.len > later.src)
+            {
+                warnings.push(format!(
+                    "The {}-to-{} map has overlapping source ranges: {} {} {} and {} {} {}",
+                    self.from,
+                    self.to,
+                    earlier.dest,
+                    earlier.src,
+                    earlier.len,
+                    later.dest,
+                    later.src,
+                    later.len
+                ));
             }
-            let (_, line) = line.split_once(":").unwrap();
-            seeds = Some(
-                line.split(" ")
-                    .filter(|x| x.len() > 0)
-                    .map(|x| {
-                        x.parse::<usize>()
-                            .with_context(|| "Unable to parse seed")
-                            .unwrap()
-                    })
-                    .collect(),
-            );
-            break;
         }
 
-        let seeds = seeds.unwrap();
-
-        let mut seeds: Vec<(usize, usize)> = seeds
-            .chunks(2)
-            .map(|x| (*x.get(0).unwrap(), *x.get(1).unwrap()))
-            .collect();
-
-        seeds.sort_by(|a: &(usize, usize), b| a.0.cmp(&b.0));
+        warnings
+    }
+}
 
-        // VV: Next process all the X-to-Y rules
-        let mut current_collection = vec![];
+impl Book {
+    pub fn parse_reader(reader: Box<dyn BufRead>) -> Result<Self> {
+        let mut maps: Vec<Map> = vec![];
+        let mut seeds: Option<Vec<usize>> = None;
+        let mut warnings = vec![];
 
-        for line in reader.by_ref() {
+        for (idx, line) in reader.lines().enumerate() {
             let line = line?;
             let line = line.trim();
+            let context = || format!("Line {}", idx + 1);
 
-            if line.len() == 0 {
+            if line.is_empty() {
                 continue;
             }
 
-            if line.ends_with("map:") {
-                if current_collection.len() > 0 {
-                    current_collection.sort_by(|a: &Mapper, b| a.src.cmp(&b.src));
-                    rules.push(current_collection.clone());
-                    current_collection.clear();
+            // VV: The first line must start with "seeds:", after that there are X-to-Y maps
+            if seeds.is_none() {
+                let Some(line) = line.strip_prefix("seeds:") else {
+                    bail!(
+                        "First line was supposed to start with \"seeds:\" but found {line} instead"
+                    );
+                };
+                seeds = Some(
+                    line.split_whitespace()
+                        .map(|x| {
+                            x.parse::<usize>()
+                                .with_context(|| format!("Parsing seed {x}"))
+                        })
+                        .collect::<Result<_>>()
+                        .with_context(context)?,
+                );
+            } else if line.ends_with("map:") {
+                let map = Map::from_header(line).with_context(context)?;
+                if maps.iter().any(|m| m.from == map.from && m.to == map.to) {
+                    bail!("{}: Duplicate {}-to-{} map", context(), map.from, map.to);
+                }
+                maps.push(map);
+            } else {
+                let mapper = Mapper::from_str(line).with_context(context)?;
+                if mapper.len == 0 {
+                    warnings.push(format!("{}: Mapper {line} has zero length", context()));
+                    continue;
+                }
+                match maps.last_mut() {
+                    Some(map) => map.rule.push(mapper),
+                    None => bail!("{}: Mapper {line} is not in a map", context()),
                 }
-                continue;
             }
+        }
 
-            let mapper = Mapper::from_str(line)?;
+        warnings.extend(maps.iter().flat_map(|map| map.overlaps()));
 
-            if mapper.len > 0 {
-                current_collection.push(mapper);
-            }
+        let seeds = seeds.with_context(|| "No seeds: line")?;
+        if seeds.len() % 2 != 0 {
+            bail!("The seeds: line must contain pairs of start and length");
         }
 
-        if current_collection.len() > 0 {
-            current_collection.sort_by(|a: &Mapper, b| a.src.cmp(&b.src));
-            rules.push(current_collection.clone());
-            current_collection.clear();
-        }
+        let mut seeds: Vec<(usize, usize)> = seeds.chunks(2).map(|x| (x[0], x[1])).collect();
+        seeds.sort_by_key(|seed| seed.0);
 
-        Ok(Self { rules, seeds })
+        Ok(Self {
+            seeds,
+            maps,
+            warnings,
+        })
     }
 
     pub fn parse_text(text: &str) -> Result<Self> {
@@ -138,24 +187,68 @@ impl Book {
     }
 
     pub fn parse_path(path: &std::path::Path) -> Result<Self> {
-        let file = std::fs::File::open(&path).with_context(|| "Cannot open input file")?;
+        let file = std::fs::File::open(path).with_context(|| "Cannot open input file")?;
         let reader = std::io::BufReader::new(file);
 
         Self::parse_reader(Box::new(reader))
     }
 
-    pub fn introduce_seed_layer(&mut self) {
-        let seed_layer: Vec<Mapper> = self
-            .seeds
+    /// Prints the warnings, or fails with all of them when @strict
+    pub fn check(&self, strict: bool) -> Result<()> {
+        if strict && !self.warnings.is_empty() {
+            bail!("{}", self.warnings.join("\n"));
+        }
+        for warning in &self.warnings {
+            eprintln!("Warning: {warning}");
+        }
+
+        Ok(())
+    }
+
+    /// The shortest sequence of maps which converts @from values into @to values. The maps form a
+    /// graph so there may be several chains, and they need not be in the order of the almanac.
+    pub fn chain(&self, from: &str, to: &str) -> Result<Vec<&Map>> {
+        if !self
+            .maps
             .iter()
-            .map(|&(start, len)| Mapper {
-                dest: start,
-                src: start,
-                len,
-                // accessed: false,
-            })
-            .collect();
-        self.rules.insert(0, seed_layer);
-        self.seeds.clear();
+            .any(|map| map.from == from || map.to == from)
+        {
+            bail!("Unknown category {from}");
+        }
+        if !self.maps.iter().any(|map| map.from == to || map.to == to) {
+            bail!("Unknown category {to}");
+        }
+
+        // VV: Breadth first search, @reached_by maps a category to the map which first reached it
+        let mut reached_by: HashMap<&str, Option<&Map>> = HashMap::from([(from, None)]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(category) = queue.pop_front() {
+            if category == to {
+                let mut chain = vec![];
+                let mut category = to;
+                while let Some(Some(map)) = reached_by.get(category) {
+                    chain.push(*map);
+                    category = &map.from;
+                }
+                chain.reverse();
+                return Ok(chain);
+            }
+
+            for map in self.maps.iter().filter(|map| map.from == category) {
+                if !reached_by.contains_key(map.to.as_str()) {
+                    reached_by.insert(&map.to, Some(map));
+                    queue.push_back(&map.to);
+                }
+            }
+        }
+
+        bail!("There is no chain of maps from {from} to {to}")
+    }
+
+    /// The RangeMap which converts @from values into @to values
+    pub fn pipeline(&self, from: &str, to: &str) -> Result<RangeMap> {
+        let chain = self.chain(from, to)?;
+        Ok(RangeMap::pipeline(chain.into_iter().map(|map| &map.rule)))
     }
 }
@@ -0,0 +1,50 @@
+use crate::model::{analyse, Model};
+use crate::race::{Kerning, Sheet};
+use anyhow::{Context, Result};
+use clap::Parser;
+
+#[derive(Parser)]
+#[command()]
+struct Args {
+    #[arg(short, long, default_value = "input/mine")]
+    input: std::path::PathBuf,
+
+    /// How the columns turn into races: none (every column is a race), full (all columns are one
+    /// race), or the sizes of groups of adjacent columns which make up each race e.g. "1,2".
+    /// Defaults to none in part a and full in part b
+    #[arg(short, long)]
+    kerning: Option<Kerning>,
+
+    /// How the boat moves: linear, quadratic:K (speed K*h^2), capped:MAX_SPEED or drag:DRAG
+    /// (speed h - DRAG)
+    #[arg(short, long, default_value = "linear")]
+    model: Model,
+}
+
+/// The main of both parts, which only differ in @kerning when --kerning is not given. Prints the
+/// report of every race and then the product of their ways to win.
+pub fn run(kerning: Kerning) -> Result<()> {
+    let args = Args::parse();
+    let path = std::env::current_dir().unwrap().join(args.input);
+    let kerning = args.kerning.unwrap_or(kerning);
+    let races = Sheet::parse_path(&path)?.races(&kerning)?;
+
+    let model = args.model.build();
+    let reports = races
+        .iter()
+        .map(|race| analyse(model.as_ref(), race))
+        .collect::<Result<Vec<_>>>()?;
+
+    for (idx, report) in reports.iter().enumerate() {
+        println!("Race {}: {report}", idx + 1);
+    }
+
+    let solution = reports
+        .iter()
+        .try_fold(1u128, |total, report| total.checked_mul(report.ways_to_win))
+        .with_context(|| "The product of the ways to win overflows")?;
+
+    println!("{solution}");
+
+    Ok(())
+}
@@ -1,95 +1,162 @@
-use anyhow::{bail, Context, Result};
-use clap::{arg, command, Parser};
+#[cfg(test)]
+use a::model::{analyse, Model};
+use a::race::Kerning;
+#[cfg(test)]
+use a::race::Sheet;
+use anyhow::Result;
 
-#[derive(Debug)]
-struct Race {
-    time: f32,
-    distance: f32,
-}
-
-#[derive(Parser)]
-#[command()]
-struct Args {
-    #[arg(short, long, default_value = "input/mine")]
-    input: std::path::PathBuf,
-}
-
-fn parse_text(text: &str) -> Result<Vec<Race>> {
-    let mut lines = text.lines();
-    let times = lines
-        .next()
-        .with_context(|| "Getting line with Time information")?
-        .strip_prefix("Time:")
-        .with_context(|| "Stripping prefix Time:")?;
-    let distances = lines
-        .next()
-        .with_context(|| "Getting line with Distance information")?
-        .strip_prefix("Distance:")
-        .with_context(|| "Stripping prefix Distance:")?;
-
-    let times = times
-        .split(' ')
-        .map(|x| x.trim())
-        .filter(|x| x.len() > 0)
-        .map(|x| {
-            x.parse::<f32>()
-                .with_context(|| format!("Parsing time {x}"))
-                .unwrap()
-        });
-    let distances = distances
-        .split(' ')
-        .map(|x| x.trim())
-        .filter(|x| x.len() > 0)
-        .map(|x| {
-            x.parse::<f32>()
-                .with_context(|| format!("Parsing distance {x}"))
-                .unwrap()
-        });
-
-    Ok(times
-        .zip(distances)
-        .map(|(time, distance)| Race { time, distance })
-        .collect())
+#[test]
+fn test_sample() -> Result<()> {
+    let sample = "Time:      7  15   30
+Distance:  9  40  200";
+    let races = Sheet::parse_text(sample)?.races(&Kerning::None)?;
+    assert_eq!(a::race::solve(&races)?, 288);
+    Ok(())
 }
 
-fn parse_path(path: &std::path::Path) -> Result<Vec<Race>> {
-    let contents = std::fs::read_to_string(&path).with_context(|| "Could not read file")?;
-
-    parse_text(&contents)
+#[test]
+fn test_brute_force() -> Result<()> {
+    use a::race::Race;
+
+    for time in 0..60u128 {
+        for distance in 0..=time * time / 4 + 1 {
+            let race = Race { time, distance };
+            let winning = (0..=time).filter(|hold| race.travelled(*hold) > distance);
+
+            assert_eq!(race.ways_to_win()?, winning.clone().count() as u128);
+            assert_eq!(
+                race.winning_holds()?,
+                winning
+                    .clone()
+                    .min()
+                    .map(|low| low..=winning.max().unwrap())
+            );
+        }
+    }
+    Ok(())
 }
 
-fn solve(races: &Vec<Race>) -> u128 {
-    // VV: quadratic inequality: -h^2 + t*h - d > 0
-    // Calc quadratic roots: (-t +- sqrt(t^2 -4*d) )/(-2)
-
-    races.iter().fold(1u128, |total, race| {
-        let d = (race.time * race.time - 4. * race.distance).sqrt();
-        let a = -(-race.time + d) / 2.;
-        let b = -(-race.time - d) / 2.;
-
-        let this = (b.ceil() - a.floor()) as u128 - 1;
+#[test]
+fn test_models() -> Result<()> {
+    use a::race::Race;
+
+    use a::model::{BoatModel, Report};
+
+    /// Hides the Monotonicity of a model so that analyse() tries every hold time
+    struct Scan(Box<dyn BoatModel>);
+
+    impl BoatModel for Scan {
+        fn distance(&self, time: u128, hold: u128) -> Option<u128> {
+            self.0.distance(time, hold)
+        }
+    }
+
+    for model in ["linear", "quadratic:2", "capped:5", "drag:3", "drag:50"] {
+        let model = model.parse::<Model>()?;
+        for time in 0..40u128 {
+            let max = (0..=time)
+                .filter_map(|hold| model.build().distance(time, hold))
+                .max()
+                .unwrap();
+            for distance in (0..=max + 1).step_by(1 + max as usize / 50) {
+                let race = Race { time, distance };
+                let report = analyse(model.build().as_ref(), &race)?;
+                assert_eq!(report, analyse(&Scan(model.build()), &race)?);
+
+                if model == Model::Linear {
+                    assert_eq!(report.winning, race.winning_holds()?);
+                }
+            }
+        }
+    }
+
+    let report = analyse(
+        &a::model::Linear,
+        &Race {
+            time: 7,
+            distance: 9,
+        },
+    )?;
+    assert_eq!(
+        report,
+        Report {
+            race: Race {
+                time: 7,
+                distance: 9
+            },
+            best_hold: 4,
+            best_distance: 12,
+            winning: Some(2..=5),
+            ways_to_win: 4,
+            margin: Some(3),
+        }
+    );
+    assert_eq!(
+        report.to_string(),
+        "time 7, record 9, best hold 4 goes 12, wins holding 2..=5 (4 ways) by up to 3"
+    );
+
+    let report = analyse(
+        &a::model::Capped { max_speed: 2 },
+        &Race {
+            time: 7,
+            distance: 9,
+        },
+    )?;
+    assert_eq!((report.best_hold, report.best_distance), (2, 10));
+    assert_eq!(report.winning, Some(2..=2));
+
+    assert!(analyse(
+        &a::model::Quadratic { k: u128::MAX },
+        &Race {
+            time: 7,
+            distance: 9
+        }
+    )
+    .is_err());
+    assert!("linear:2".parse::<Model>().is_err());
+    assert!("drag".parse::<Model>().is_err());
+    assert!("cubic:3".parse::<Model>().is_err());
 
-        this * total
-    })
+    Ok(())
 }
 
 #[test]
-fn test_sample() -> Result<()> {
-    let sample = "Time:      7  15   30
-Distance:  9  40  200";
-    let races = parse_text(sample)?;
-    assert_eq!(solve(&races), 288);
+fn test_kerning() -> Result<()> {
+    use a::race::Race;
+
+    let sheet = Sheet::parse_text("Time:      7  15   30\nDistance:  9  40  200")?;
+    let race = |time, distance| Race { time, distance };
+
+    assert_eq!(sheet.times, ["7", "15", "30"]);
+    assert_eq!(
+        sheet.races(&"none".parse()?)?,
+        [race(7, 9), race(15, 40), race(30, 200)]
+    );
+    assert_eq!(sheet.races(&"full".parse()?)?, [race(71530, 940200)]);
+    assert_eq!(
+        sheet.races(&"1,2".parse()?)?,
+        [race(7, 9), race(1530, 40200)]
+    );
+    assert_eq!(
+        sheet.races(&"2, 1".parse()?)?,
+        [race(715, 940), race(30, 200)]
+    );
+
+    assert!(sheet.races(&"1,1".parse()?).is_err());
+    assert!(sheet.races(&"0,3".parse()?).is_err());
+    assert!("1,x".parse::<Kerning>().is_err());
+    assert!(Sheet::parse_text("Time: 7 15\nDistance: 9").is_err());
+    assert!(Sheet::parse_text("Time: 7 -15\nDistance: 9 40").is_err());
+    assert!(
+        Sheet::parse_text("Time: 99999999999999999999999999999999999999999\nDistance: 9")?
+            .races(&Kerning::None)
+            .is_err()
+    );
+
     Ok(())
 }
 
 fn main() -> Result<()> {
-    let args = Args::parse();
-    let path = std::env::current_dir().unwrap().join(args.input);
-    let races = parse_path(&path)?;
-
-    let solution = solve(&races);
-
-    println!("{solution}");
-
-    Ok(())
+    a::cli::run(Kerning::None)
 }
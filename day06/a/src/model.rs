@@ -0,0 +1,245 @@
+use crate::race::Race;
+use anyhow::{bail, Context, Result};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monotonicity {
+    /// The distance never decreases up to a peak and strictly decreases after it, so the winning
+    /// hold times are one interval around the peak and bisection finds its ends
+    Unimodal,
+    /// Every hold time has to be tried
+    Unknown,
+}
+
+/// How far a boat goes depending on how long the button is held
+pub trait BoatModel {
+    /// The distance in a race of @time ms when holding the button for @hold <= @time ms, None if
+    /// it overflows
+    fn distance(&self, time: u128, hold: u128) -> Option<u128>;
+
+    fn monotonicity(&self) -> Monotonicity {
+        Monotonicity::Unknown
+    }
+}
+
+/// Speed h after holding for h ms (the puzzle)
+pub struct Linear;
+
+impl BoatModel for Linear {
+    fn distance(&self, time: u128, hold: u128) -> Option<u128> {
+        hold.checked_mul(time - hold)
+    }
+
+    fn monotonicity(&self) -> Monotonicity {
+        Monotonicity::Unimodal
+    }
+}
+
+/// Speed k*h^2 after holding for h ms
+pub struct Quadratic {
+    pub k: u128,
+}
+
+impl BoatModel for Quadratic {
+    fn distance(&self, time: u128, hold: u128) -> Option<u128> {
+        self.k
+            .checked_mul(hold)?
+            .checked_mul(hold)?
+            .checked_mul(time - hold)
+    }
+
+    fn monotonicity(&self) -> Monotonicity {
+        Monotonicity::Unimodal
+    }
+}
+
+/// Speed h after holding for h ms but never more than @max_speed
+pub struct Capped {
+    pub max_speed: u128,
+}
+
+impl BoatModel for Capped {
+    fn distance(&self, time: u128, hold: u128) -> Option<u128> {
+        hold.min(self.max_speed).checked_mul(time - hold)
+    }
+
+    fn monotonicity(&self) -> Monotonicity {
+        Monotonicity::Unimodal
+    }
+}
+
+/// Speed h after holding for h ms, minus a constant @drag. The boat does not move at all unless
+/// the button is held for longer than @drag ms.
+pub struct Drag {
+    pub drag: u128,
+}
+
+impl BoatModel for Drag {
+    fn distance(&self, time: u128, hold: u128) -> Option<u128> {
+        hold.saturating_sub(self.drag).checked_mul(time - hold)
+    }
+
+    fn monotonicity(&self) -> Monotonicity {
+        Monotonicity::Unimodal
+    }
+}
+
+/// The built-in models
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Model {
+    Linear,
+    Quadratic(u128),
+    Capped(u128),
+    Drag(u128),
+}
+
+impl Model {
+    pub fn build(&self) -> Box<dyn BoatModel> {
+        match *self {
+            Model::Linear => Box::new(Linear),
+            Model::Quadratic(k) => Box::new(Quadratic { k }),
+            Model::Capped(max_speed) => Box::new(Capped { max_speed }),
+            Model::Drag(drag) => Box::new(Drag { drag }),
+        }
+    }
+}
+
+impl std::str::FromStr for Model {
+    type Err = anyhow::Error;
+
+    /// One of linear, quadratic:K, capped:MAX_SPEED, drag:DRAG
+    fn from_str(text: &str) -> Result<Self> {
+        let (name, parameter) = match text.split_once(':') {
+            Some((name, parameter)) => (
+                name,
+                Some(
+                    parameter
+                        .parse::<u128>()
+                        .with_context(|| format!("Invalid parameter of model {text}"))?,
+                ),
+            ),
+            None => (text, None),
+        };
+
+        Ok(match (name, parameter) {
+            ("linear", None) => Model::Linear,
+            ("quadratic", Some(k)) => Model::Quadratic(k),
+            ("capped", Some(max_speed)) => Model::Capped(max_speed),
+            ("drag", Some(drag)) => Model::Drag(drag),
+            ("linear", Some(_)) => bail!("The linear model has no parameter"),
+            ("quadratic" | "capped" | "drag", None) => bail!("The {name} model needs a parameter"),
+            _ => bail!("Unknown model {name}"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub race: Race,
+    /// The hold time which goes the furthest, the last one if several tie
+    pub best_hold: u128,
+    pub best_distance: u128,
+    /// From the first to the last hold time which beats the record. Only models with an Unknown
+    /// Monotonicity may lose some races in between.
+    pub winning: Option<RangeInclusive<u128>>,
+    pub ways_to_win: u128,
+    /// How much further than the record the best hold goes, None if the record cannot be beaten
+    pub margin: Option<u128>,
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "time {}, record {}, best hold {} goes {}",
+            self.race.time, self.race.distance, self.best_hold, self.best_distance
+        )?;
+        match (&self.winning, self.margin) {
+            (Some(winning), Some(margin)) => write!(
+                f,
+                ", wins holding {}..={} ({} ways) by up to {}",
+                winning.start(),
+                winning.end(),
+                self.ways_to_win,
+                margin
+            ),
+            _ => write!(f, ", cannot win"),
+        }
+    }
+}
+
+/// The first value in [low, high) for which @predicate holds, @high if there is none.
+/// @predicate must be false up to some value and true from then on.
+fn partition_point(
+    mut low: u128,
+    mut high: u128,
+    mut predicate: impl FnMut(u128) -> Result<bool>,
+) -> Result<u128> {
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if predicate(mid)? {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Ok(low)
+}
+
+pub fn analyse(model: &dyn BoatModel, race: &Race) -> Result<Report> {
+    let time = race.time;
+    let record = race.distance;
+    let distance = |hold: u128| {
+        model
+            .distance(time, hold)
+            .with_context(|| format!("The distance of holding {hold} ms out of {time} overflows"))
+    };
+
+    let (best_hold, best_distance, winning, ways_to_win) = match model.monotonicity() {
+        Monotonicity::Unimodal => {
+            // VV: The peak is the first hold time after which the distance drops
+            let best_hold =
+                partition_point(0, time, |hold| Ok(distance(hold)? > distance(hold + 1)?))?;
+            let best_distance = distance(best_hold)?;
+
+            if best_distance > record {
+                let low = partition_point(0, best_hold, |hold| Ok(distance(hold)? > record))?;
+                let high = partition_point(best_hold, time.saturating_add(1), |hold| {
+                    Ok(distance(hold)? <= record)
+                })? - 1;
+                (best_hold, best_distance, Some(low..=high), high - low + 1)
+            } else {
+                (best_hold, best_distance, None, 0)
+            }
+        }
+        Monotonicity::Unknown => {
+            let (mut best_hold, mut best_distance) = (0, distance(0)?);
+            let mut winning: Option<RangeInclusive<u128>> = None;
+            let mut ways_to_win = 0;
+
+            for hold in 0..=time {
+                let travelled = distance(hold)?;
+                if travelled >= best_distance {
+                    (best_hold, best_distance) = (hold, travelled);
+                }
+                if travelled > record {
+                    ways_to_win += 1;
+                    winning = Some(winning.map_or(hold, |winning| *winning.start())..=hold);
+                }
+            }
+
+            (best_hold, best_distance, winning, ways_to_win)
+        }
+    };
+
+    Ok(Report {
+        race: *race,
+        best_hold,
+        best_distance,
+        winning,
+        ways_to_win,
+        margin: best_distance
+            .checked_sub(record)
+            .filter(|margin| *margin > 0),
+    })
+}
@@ -0,0 +1,195 @@
+use anyhow::{bail, Context, Result};
+use std::ops::RangeInclusive;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Race {
+    pub time: u128,
+    /// The record distance
+    pub distance: u128,
+}
+
+impl Race {
+    /// How far the boat goes when holding the button for @hold ms
+    pub fn travelled(&self, hold: u128) -> u128 {
+        hold * (self.time - hold)
+    }
+
+    fn wins(&self, hold: u128) -> bool {
+        self.travelled(hold) > self.distance
+    }
+
+    /// The hold times which beat the record, None if there are none.
+    ///
+    /// The boat wins when -h^2 + t*h - d > 0 i.e. strictly between the roots
+    /// (t +- sqrt(t^2 - 4*d)) / 2. The winning hold times are symmetric around t/2 so this only
+    /// looks for the smallest one. Everything is in integers: isqrt() gets within 1 of the lower
+    /// root and then the first winning integer is found by stepping, which also takes care of roots
+    /// which land exactly on an integer (those tie the record instead of beating it).
+    pub fn winning_holds(&self) -> Result<Option<RangeInclusive<u128>>> {
+        let t = self.time;
+        let discriminant = t
+            .checked_mul(t)
+            .with_context(|| format!("Race time {t} is too large"))?;
+
+        let Some(discriminant) = discriminant.checked_sub(self.distance.saturating_mul(4)) else {
+            return Ok(None);
+        };
+
+        let mut low = (t - discriminant.isqrt()) / 2;
+        while low > 0 && self.wins(low - 1) {
+            low -= 1;
+        }
+        while low <= t / 2 && !self.wins(low) {
+            low += 1;
+        }
+
+        Ok((low <= t / 2).then(|| low..=t - low))
+    }
+
+    /// How many hold times beat the record
+    pub fn ways_to_win(&self) -> Result<u128> {
+        Ok(self
+            .winning_holds()?
+            .map_or(0, |holds| holds.end() - holds.start() + 1))
+    }
+}
+
+/// The product of the ways to win each race
+pub fn solve(races: &[Race]) -> Result<u128> {
+    races.iter().try_fold(1u128, |total, race| {
+        total
+            .checked_mul(race.ways_to_win()?)
+            .with_context(|| "The product of the ways to win overflows")
+    })
+}
+
+/// How the columns of the sheet turn into races
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kerning {
+    /// Every column is a race (part a)
+    None,
+    /// All columns are one race (part b)
+    Full,
+    /// Each group of that many adjacent columns is a race e.g. [1, 2] merges the last 2 columns
+    Groups(Vec<usize>),
+}
+
+impl std::str::FromStr for Kerning {
+    type Err = anyhow::Error;
+
+    /// One of none, full, or the sizes of the groups separated by commas e.g. "1,2"
+    fn from_str(text: &str) -> Result<Self> {
+        Ok(match text {
+            "none" => Kerning::None,
+            "full" => Kerning::Full,
+            groups => Kerning::Groups(
+                groups
+                    .split(',')
+                    .map(|size| {
+                        size.trim()
+                            .parse::<usize>()
+                            .with_context(|| format!("Invalid group size {size} in {text}"))
+                    })
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+}
+
+/// The columns of the Time: and Distance: lines exactly as they are written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sheet {
+    pub times: Vec<String>,
+    pub distances: Vec<String>,
+}
+
+impl Sheet {
+    pub fn parse_text(text: &str) -> Result<Self> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let times = lines
+            .next()
+            .with_context(|| "Getting line with Time information")?
+            .strip_prefix("Time:")
+            .with_context(|| "Stripping prefix Time:")?;
+        let distances = lines
+            .next()
+            .with_context(|| "Getting line with Distance information")?
+            .strip_prefix("Distance:")
+            .with_context(|| "Stripping prefix Distance:")?;
+
+        let columns = |line: &str, what: &str| -> Result<Vec<String>> {
+            line.split_whitespace()
+                .map(|x| {
+                    if x.chars().all(|c| c.is_ascii_digit()) {
+                        Ok(x.to_string())
+                    } else {
+                        bail!("Invalid {what} {x}")
+                    }
+                })
+                .collect()
+        };
+
+        let times = columns(times, "time")?;
+        let distances = columns(distances, "distance")?;
+
+        if times.len() != distances.len() {
+            bail!(
+                "There are {} times but {} distances",
+                times.len(),
+                distances.len()
+            );
+        }
+        if times.is_empty() {
+            bail!("There are no races");
+        }
+
+        Ok(Self { times, distances })
+    }
+
+    pub fn parse_path(path: &std::path::Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path).with_context(|| "Could not read file")?;
+
+        Self::parse_text(&contents)
+    }
+
+    /// Merges the columns into races according to @kerning
+    pub fn races(&self, kerning: &Kerning) -> Result<Vec<Race>> {
+        let columns = self.times.len();
+        let groups = match kerning {
+            Kerning::None => vec![1; columns],
+            Kerning::Full => vec![columns],
+            Kerning::Groups(groups) => {
+                if groups.contains(&0) {
+                    bail!("Groups of columns cannot be empty");
+                }
+                if groups.iter().sum::<usize>() != columns {
+                    bail!(
+                        "The groups {groups:?} cover {} columns but there are {columns}",
+                        groups.iter().sum::<usize>()
+                    );
+                }
+                groups.clone()
+            }
+        };
+
+        let mut races = vec![];
+        let mut start = 0;
+
+        for (idx, size) in groups.into_iter().enumerate() {
+            let merge = |columns: &[String], what: &str| {
+                let digits = columns[start..start + size].concat();
+                digits.parse::<u128>().with_context(|| {
+                    format!("The {what} {digits} of race {} is too large", idx + 1)
+                })
+            };
+
+            races.push(Race {
+                time: merge(&self.times, "time")?,
+                distance: merge(&self.distances, "distance")?,
+            });
+            start += size;
+        }
+
+        Ok(races)
+    }
+}
@@ -0,0 +1,6 @@
+#[path = "../../a/src/cli.rs"]
+pub mod cli;
+#[path = "../../a/src/model.rs"]
+pub mod model;
+#[path = "../../a/src/race.rs"]
+pub mod race;
@@ -0,0 +1,303 @@
+use crate::engine::{parse_text, Card, Hand, HandType, Rules};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+
+/// The cards which can still be drawn and how many copies of each are left
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deck {
+    pub cards: BTreeMap<Card, usize>,
+}
+
+impl Deck {
+    /// @copies of every rank, or one card of every rank and suit if the Rules have suits
+    pub fn full(rules: &Rules, copies: usize) -> Self {
+        let mut cards = BTreeMap::new();
+
+        for rank in &rules.ordering {
+            if rules.suits.is_empty() {
+                cards.insert(
+                    Card {
+                        rank: *rank,
+                        suit: None,
+                    },
+                    copies,
+                );
+            } else {
+                for suit in &rules.suits {
+                    cards.insert(
+                        Card {
+                            rank: *rank,
+                            suit: Some(*suit),
+                        },
+                        1,
+                    );
+                }
+            }
+        }
+
+        Self { cards }
+    }
+
+    pub fn remove(&mut self, cards: &[Card]) -> Result<()> {
+        for card in cards {
+            match self.cards.get_mut(card) {
+                Some(copies) if *copies > 0 => *copies -= 1,
+                _ => bail!("There is no {card} left in the deck"),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.values().sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How many of the ways to complete a hand end up with each type
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Distribution {
+    pub ways: BTreeMap<HandType, u128>,
+    pub total: u128,
+}
+
+impl Distribution {
+    pub fn probability(&self, hand_type: HandType) -> f64 {
+        self.ways.get(&hand_type).copied().unwrap_or(0) as f64 / self.total as f64
+    }
+}
+
+impl std::fmt::Display for Distribution {
+    /// The types the hand can end up with, strongest first
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let types = self
+            .ways
+            .keys()
+            .rev()
+            .map(|hand_type| format!("{hand_type} {:.4}%", 100.0 * self.probability(*hand_type)))
+            .collect::<Vec<_>>();
+        write!(f, "{}", types.join(", "))
+    }
+}
+
+fn choose(n: usize, k: usize) -> u128 {
+    (0..k).fold(1u128, |ways, idx| {
+        ways * (n - idx) as u128 / (idx as u128 + 1)
+    })
+}
+
+/// Every way to draw @draw more cards from @deck and add them to @partial. Draws are grouped by
+/// the cards they contain (not their order) and weighted by how many ways there are to draw them.
+pub fn distribution(
+    rules: &Rules,
+    partial: &[Card],
+    deck: &Deck,
+    draw: usize,
+) -> Result<Distribution> {
+    if draw > deck.len() {
+        bail!("Cannot draw {draw} cards from a deck of {}", deck.len());
+    }
+
+    fn visit(
+        rules: &Rules,
+        kinds: &[(Card, usize)],
+        hand: &mut Vec<Card>,
+        draw: usize,
+        ways: u128,
+        distribution: &mut Distribution,
+    ) -> Result<()> {
+        if draw == 0 {
+            let hand_type = rules.evaluate(hand)?.hand_type;
+            *distribution.ways.entry(hand_type).or_default() += ways;
+            distribution.total += ways;
+            return Ok(());
+        }
+
+        let Some((&(card, copies), rest)) = kinds.split_first() else {
+            return Ok(());
+        };
+
+        for count in 0..=copies.min(draw) {
+            hand.extend(std::iter::repeat_n(card, count));
+            visit(
+                rules,
+                rest,
+                hand,
+                draw - count,
+                ways * choose(copies, count),
+                distribution,
+            )?;
+            hand.truncate(hand.len() - count);
+        }
+
+        Ok(())
+    }
+
+    let kinds = deck
+        .cards
+        .iter()
+        .filter(|(_, copies)| **copies > 0)
+        .map(|(card, copies)| (*card, *copies))
+        .collect::<Vec<_>>();
+
+    let mut distribution = Distribution::default();
+    visit(
+        rules,
+        &kinds,
+        &mut partial.to_vec(),
+        draw,
+        1,
+        &mut distribution,
+    )?;
+
+    Ok(distribution)
+}
+
+/// The rank of each hand (1 is the weakest) in the order the @hands are given
+pub fn ranks(hands: &[Hand]) -> Vec<usize> {
+    let mut order = (0..hands.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| hands[*a].evaluation.key().cmp(&hands[*b].evaluation.key()));
+
+    let mut ranks = vec![0; hands.len()];
+    for (rank, idx) in order.into_iter().enumerate() {
+        ranks[idx] = rank + 1;
+    }
+    ranks
+}
+
+#[derive(Debug, Clone)]
+pub struct HandReport {
+    pub hand: Hand,
+    pub rank: usize,
+    /// The types the hand could have had if its wildcards were drawn again from the deck
+    pub distribution: Distribution,
+    /// The rank of the hand if no card were a wildcard
+    pub rank_without_wildcards: usize,
+}
+
+impl HandReport {
+    /// How many places the hand would move up (negative for down) if wildcards were disabled
+    pub fn moves(&self) -> i64 {
+        self.rank_without_wildcards as i64 - self.rank as i64
+    }
+}
+
+impl std::fmt::Display for HandReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} rank {} {} moves {:+} without wildcards, redrawing wildcards: {}",
+            self.hand.cards,
+            self.hand.bet,
+            self.rank,
+            self.hand.evaluation.hand_type,
+            self.moves(),
+            self.distribution
+        )
+    }
+}
+
+/// Reports every hand of @text in the order they are given. The deck of each hand is a full
+/// deck of @copies without the cards of the hand which are not wildcards.
+pub fn analyse_hands(text: &str, rules: &Rules, copies: usize) -> Result<Vec<HandReport>> {
+    let hands = parse_text(text, rules)?;
+    let ranks_with_wildcards = ranks(&hands);
+
+    let plain = Rules {
+        wildcards: vec![],
+        ..rules.clone()
+    };
+    let ranks_without_wildcards = ranks(&parse_text(text, &plain)?);
+
+    hands
+        .into_iter()
+        .zip(
+            ranks_with_wildcards
+                .into_iter()
+                .zip(ranks_without_wildcards),
+        )
+        .map(|(hand, (rank, rank_without_wildcards))| {
+            let cards = rules.parse_hand(&hand.cards)?;
+            let (wildcards, naturals): (Vec<Card>, Vec<Card>) =
+                cards.into_iter().partition(|card| rules.is_wildcard(*card));
+
+            let mut deck = Deck::full(rules, copies);
+            deck.remove(&naturals)
+                .with_context(|| format!("Dealing hand {}", hand.cards))?;
+            let distribution = distribution(rules, &naturals, &deck, wildcards.len())?;
+
+            Ok(HandReport {
+                hand,
+                rank,
+                distribution,
+                rank_without_wildcards,
+            })
+        })
+        .collect()
+}
+
+/// Command line options of the analysis mode
+#[derive(Debug, clap::Args)]
+pub struct AnalysisOptions {
+    /// Instead of solving, print the chance of each hand type when completing this partial hand
+    /// with cards from the deck e.g. "KKJ"
+    #[arg(long)]
+    pub partial: Option<String>,
+
+    /// How many cards the partial hand is completed to
+    #[arg(long, default_value_t = 5)]
+    pub complete_to: usize,
+
+    /// Cards which are no longer in the deck besides the partial hand e.g. those of other players
+    #[arg(long, default_value = "")]
+    pub dealt: String,
+
+    /// Instead of solving, print the rank and hand type of every hand, its chances if its
+    /// wildcards were drawn again, and how far it would move if there were no wildcards
+    #[arg(long)]
+    pub analyse: bool,
+
+    /// How many copies of each rank the deck has when cards have no suits
+    #[arg(long, default_value_t = 4)]
+    pub copies: usize,
+}
+
+impl AnalysisOptions {
+    /// Prints the analysis of the input at @path if one was asked for and returns whether it did
+    pub fn run(&self, rules: &Rules, path: &std::path::Path) -> Result<bool> {
+        if let Some(partial) = &self.partial {
+            let partial = rules.parse_hand(partial)?;
+            let draw = self
+                .complete_to
+                .checked_sub(partial.len())
+                .context("The partial hand already has too many cards")?;
+
+            let mut deck = Deck::full(rules, self.copies);
+            deck.remove(&partial)?;
+            deck.remove(&rules.parse_hand(&self.dealt)?)?;
+
+            let distribution = distribution(rules, &partial, &deck, draw)?;
+            for (hand_type, ways) in distribution.ways.iter().rev() {
+                println!(
+                    "{hand_type}: {ways}/{} ({:.4}%)",
+                    distribution.total,
+                    100.0 * distribution.probability(*hand_type)
+                );
+            }
+            return Ok(true);
+        }
+
+        if self.analyse {
+            let text = std::fs::read_to_string(path).with_context(|| "Reading input file")?;
+            for report in analyse_hands(&text, rules, self.copies)? {
+                println!("{report}");
+            }
+            return Ok(true);
+        }
+
+        Ok(false)
+    }
+}
@@ -0,0 +1,637 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+
+/// From the weakest to the strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HandType {
+    HighCard,
+    OnePair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+    FiveOfAKind,
+}
+
+/// Straights and flushes need at least this many cards
+pub const SEQUENCE_CARDS: usize = 5;
+
+impl HandType {
+    pub const ALL: [HandType; 10] = [
+        HandType::HighCard,
+        HandType::OnePair,
+        HandType::TwoPair,
+        HandType::ThreeOfAKind,
+        HandType::Straight,
+        HandType::Flush,
+        HandType::FullHouse,
+        HandType::FourOfAKind,
+        HandType::StraightFlush,
+        HandType::FiveOfAKind,
+    ];
+
+    /// The types of the puzzle, they only look at groups of equal cards
+    pub const CLASSIC: [HandType; 7] = [
+        HandType::HighCard,
+        HandType::OnePair,
+        HandType::TwoPair,
+        HandType::ThreeOfAKind,
+        HandType::FullHouse,
+        HandType::FourOfAKind,
+        HandType::FiveOfAKind,
+    ];
+
+    /// The sizes of the groups of equal cards which make up the type, largest first. None for
+    /// types which are about the order or suits of the cards.
+    pub fn pattern(&self) -> Option<&'static [usize]> {
+        match self {
+            HandType::HighCard => Some(&[1]),
+            HandType::OnePair => Some(&[2]),
+            HandType::TwoPair => Some(&[2, 2]),
+            HandType::ThreeOfAKind => Some(&[3]),
+            HandType::FullHouse => Some(&[3, 2]),
+            HandType::FourOfAKind => Some(&[4]),
+            HandType::FiveOfAKind => Some(&[5]),
+            HandType::Straight | HandType::Flush | HandType::StraightFlush => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            HandType::HighCard => "high-card",
+            HandType::OnePair => "one-pair",
+            HandType::TwoPair => "two-pair",
+            HandType::ThreeOfAKind => "three-of-a-kind",
+            HandType::Straight => "straight",
+            HandType::Flush => "flush",
+            HandType::FullHouse => "full-house",
+            HandType::FourOfAKind => "four-of-a-kind",
+            HandType::StraightFlush => "straight-flush",
+            HandType::FiveOfAKind => "five-of-a-kind",
+        }
+    }
+}
+
+impl std::fmt::Display for HandType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for HandType {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        HandType::ALL
+            .into_iter()
+            .find(|hand_type| hand_type.name() == text)
+            .with_context(|| format!("Unknown hand type {text}"))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card {
+    pub rank: char,
+    /// None unless the Rules have suits
+    pub suit: Option<char>,
+}
+
+impl std::fmt::Display for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.rank)?;
+        if let Some(suit) = self.suit {
+            write!(f, "{suit}")?;
+        }
+        Ok(())
+    }
+}
+
+/// How to order hands of the same type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum TieBreak {
+    /// Compare the cards in the order they are dealt (the puzzle)
+    Dealt,
+    /// Compare the cards from the last one to the first
+    Reversed,
+    /// Compare the cards of the largest groups first like poker, wildcards count as the cards
+    /// they stand for
+    Grouped,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Every rank from the weakest to the strongest, straights follow this order
+    pub ordering: Vec<char>,
+    /// Ranks which stand for whichever card makes the strongest hand type
+    pub wildcards: Vec<char>,
+    /// A hand is the strongest of these types that it can make, HighCard must be one of them
+    pub hand_types: Vec<HandType>,
+    pub tie_break: TieBreak,
+    /// The suits which follow every rank in the input e.g. "shdc", empty if cards have no suits
+    pub suits: Vec<char>,
+    /// Score hands with more cards than this by their best sub-hand of this many cards
+    pub best_of: Option<usize>,
+}
+
+impl Rules {
+    pub fn part_a() -> Self {
+        Self {
+            ordering: "23456789TJQKA".chars().collect(),
+            wildcards: vec![],
+            hand_types: HandType::CLASSIC.to_vec(),
+            tie_break: TieBreak::Dealt,
+            suits: vec![],
+            best_of: None,
+        }
+    }
+
+    /// J is a joker and the weakest card
+    pub fn part_b() -> Self {
+        Self {
+            ordering: "J23456789TQKA".chars().collect(),
+            wildcards: vec!['J'],
+            ..Self::part_a()
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.ordering.len() > u8::MAX as usize + 1 {
+            bail!(
+                "The ordering has {} cards but at most {} are supported",
+                self.ordering.len(),
+                u8::MAX as usize + 1
+            );
+        }
+        for (idx, card) in self.ordering.iter().enumerate() {
+            if self.ordering[..idx].contains(card) {
+                bail!("Card {card} appears twice in the ordering");
+            }
+        }
+        for (idx, suit) in self.suits.iter().enumerate() {
+            if self.suits[..idx].contains(suit) {
+                bail!("Suit {suit} appears twice");
+            }
+        }
+        for card in &self.wildcards {
+            if !self.ordering.contains(card) {
+                bail!("Wildcard {card} is not in the ordering");
+            }
+        }
+        if !self.hand_types.contains(&HandType::HighCard) {
+            bail!("The {} hand type cannot be disabled", HandType::HighCard);
+        }
+        for hand_type in [HandType::Flush, HandType::StraightFlush] {
+            if self.hand_types.contains(&hand_type) && self.suits.is_empty() {
+                bail!("The {hand_type} hand type needs suits");
+            }
+        }
+        if self.best_of == Some(0) {
+            bail!("Hands must be scored by at least 1 card");
+        }
+        Ok(())
+    }
+
+    /// The position of @rank in the ordering, stronger ranks have larger strengths
+    pub fn strength(&self, rank: char) -> Result<u8> {
+        self.ordering
+            .iter()
+            .position(|c| *c == rank)
+            .with_context(|| format!("Unknown card {rank}"))
+            .and_then(|strength| {
+                u8::try_from(strength)
+                    .with_context(|| format!("Card {rank} is too far into the ordering"))
+            })
+    }
+
+    pub fn is_wildcard(&self, card: Card) -> bool {
+        self.wildcards.contains(&card.rank)
+    }
+
+    /// Each card is a rank followed by a suit if the Rules have suits
+    pub fn parse_hand(&self, text: &str) -> Result<Vec<Card>> {
+        let chars = text.chars().collect::<Vec<_>>();
+
+        let cards = if self.suits.is_empty() {
+            chars
+                .into_iter()
+                .map(|rank| Card { rank, suit: None })
+                .collect::<Vec<_>>()
+        } else {
+            if chars.len() % 2 != 0 {
+                bail!("Every card of {text} needs a rank and a suit");
+            }
+            chars
+                .chunks(2)
+                .map(|card| match self.suits.contains(&card[1]) {
+                    true => Ok(Card {
+                        rank: card[0],
+                        suit: Some(card[1]),
+                    }),
+                    false => bail!("Unknown suit {}", card[1]),
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        for card in &cards {
+            self.strength(card.rank)?;
+        }
+
+        Ok(cards)
+    }
+
+    /// Finds the strongest enabled type that @cards can make and what their wildcards stand for.
+    /// Hands with more than @best_of cards are as strong as their strongest sub-hand.
+    pub fn evaluate(&self, cards: &[Card]) -> Result<Evaluation> {
+        if cards.is_empty() {
+            bail!("A hand needs at least 1 card");
+        }
+
+        let size = match self.best_of {
+            Some(best_of) if best_of < cards.len() => best_of,
+            _ => cards.len(),
+        };
+
+        let mut best: Option<Evaluation> = None;
+        for positions in combinations(cards.len(), size) {
+            let evaluation = self.evaluate_exact(cards, positions)?;
+            if best
+                .as_ref()
+                .is_none_or(|best| evaluation.key() > best.key())
+            {
+                best = Some(evaluation);
+            }
+        }
+
+        best.context("No sub-hands")
+    }
+
+    /// The ranks which the wildcards must become for the @naturals to make a straight of @len
+    /// cards, the highest straight if there are several
+    fn straight(&self, naturals: &[Card], len: usize) -> Option<Vec<char>> {
+        let sequence = self
+            .ordering
+            .iter()
+            .copied()
+            .filter(|rank| !self.wildcards.contains(rank))
+            .collect::<Vec<_>>();
+        if len < SEQUENCE_CARDS || len > sequence.len() {
+            return None;
+        }
+
+        let mut positions = naturals
+            .iter()
+            .map(|card| sequence.iter().position(|rank| *rank == card.rank))
+            .collect::<Option<Vec<_>>>()?;
+        positions.sort_unstable();
+        if positions.windows(2).any(|pair| pair[0] == pair[1]) {
+            return None;
+        }
+
+        let start = positions
+            .first()
+            .map_or(sequence.len() - len, |low| (*low).min(sequence.len() - len));
+        if positions.last().is_some_and(|high| *high >= start + len) {
+            return None;
+        }
+
+        Some(
+            (start..start + len)
+                .filter(|position| !positions.contains(position))
+                .map(|position| sequence[position])
+                .collect(),
+        )
+    }
+
+    /// The suit that @naturals and the wildcards can all share in a hand of @len cards
+    fn flush(&self, naturals: &[Card], len: usize) -> Option<char> {
+        if len < SEQUENCE_CARDS {
+            return None;
+        }
+
+        match naturals.first() {
+            Some(first) => naturals
+                .iter()
+                .all(|card| card.suit == first.suit)
+                .then_some(first.suit)
+                .flatten(),
+            None => self.suits.first().copied(),
+        }
+    }
+
+    /// Evaluates the sub-hand made of the cards at @positions
+    fn evaluate_exact(&self, cards: &[Card], positions: Vec<usize>) -> Result<Evaluation> {
+        let hand = positions.iter().map(|&idx| cards[idx]).collect::<Vec<_>>();
+        let naturals = hand
+            .iter()
+            .copied()
+            .filter(|card| !self.is_wildcard(*card))
+            .collect::<Vec<_>>();
+        let wildcards = hand.len() - naturals.len();
+
+        // VV: (rank, copies) of the cards which are not wildcards, largest group first and then
+        // strongest rank first
+        let mut groups: Vec<(char, usize)> = vec![];
+        for card in &naturals {
+            match groups.iter_mut().find(|(rank, _)| *rank == card.rank) {
+                Some((_, copies)) => *copies += 1,
+                None => groups.push((card.rank, 1)),
+            }
+        }
+        groups.sort_by_key(|&(rank, copies)| {
+            std::cmp::Reverse((copies, self.strength(rank).unwrap_or(0)))
+        });
+
+        // VV: The wildcards fill the gaps between the groups and the pattern of a type, the k-th
+        // largest group is the best candidate for the k-th group of the pattern
+        let missing = |pattern: &[usize]| {
+            pattern
+                .iter()
+                .enumerate()
+                .map(|(idx, size)| size.saturating_sub(groups.get(idx).map_or(0, |g| g.1)))
+                .sum::<usize>()
+        };
+        let straight = self.straight(&naturals, hand.len());
+        let flush = self.flush(&naturals, hand.len());
+
+        let hand_type = self
+            .hand_types
+            .iter()
+            .copied()
+            .filter(|hand_type| match hand_type.pattern() {
+                Some(pattern) => missing(pattern) <= wildcards,
+                None => match hand_type {
+                    HandType::Straight => straight.is_some(),
+                    HandType::Flush => flush.is_some(),
+                    _ => straight.is_some() && flush.is_some(),
+                },
+            })
+            .max()
+            .context("The hand has no hand type")?;
+
+        // VV: Decide which rank each wildcard becomes. Groups that a pattern needs but the hand
+        // lacks, flushes, and wildcards the type has no use for take the strongest ranks which are
+        // not in the hand, so that the leftovers cannot form a group the type does not have.
+        let mut unused =
+            self.ordering.iter().rev().copied().filter(|rank| {
+                !self.wildcards.contains(rank) && !groups.iter().any(|g| g.0 == *rank)
+            });
+        let mut ranks = vec![];
+        match hand_type.pattern() {
+            Some(pattern) => {
+                for (idx, size) in pattern.iter().enumerate() {
+                    let (rank, copies) = match groups.get(idx) {
+                        Some(&group) => group,
+                        None => (unused.next().context("Ran out of ranks")?, 0),
+                    };
+                    ranks.extend(std::iter::repeat_n(rank, size.saturating_sub(copies)));
+                }
+            }
+            None if hand_type == HandType::Flush => ranks.extend(unused.by_ref().take(wildcards)),
+            None => ranks.extend(straight.iter().flatten().rev()),
+        }
+        let leftovers = wildcards.saturating_sub(ranks.len());
+        ranks.extend(unused.take(leftovers));
+
+        let suit = match hand_type {
+            HandType::Flush | HandType::StraightFlush => flush,
+            _ => None,
+        };
+        let mut ranks = ranks.into_iter();
+        let substitutions = hand
+            .iter()
+            .filter(|card| self.is_wildcard(**card))
+            .map(|card| {
+                let rank = ranks.next().unwrap_or(card.rank);
+                (
+                    *card,
+                    Card {
+                        rank,
+                        suit: suit.or(card.suit),
+                    },
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let strengths = hand
+            .iter()
+            .map(|card| self.strength(card.rank))
+            .collect::<Result<Vec<_>>>()?;
+
+        let tie_break = match self.tie_break {
+            TieBreak::Dealt => strengths,
+            TieBreak::Reversed => strengths.into_iter().rev().collect(),
+            TieBreak::Grouped => {
+                let mut substituted = substitutions.iter().map(|s| s.1.rank);
+                let ranks = hand
+                    .iter()
+                    .map(|card| match self.is_wildcard(*card) {
+                        true => substituted.next().unwrap_or(card.rank),
+                        false => card.rank,
+                    })
+                    .collect::<Vec<_>>();
+
+                let mut copies: HashMap<char, usize> = HashMap::new();
+                for rank in &ranks {
+                    *copies.entry(*rank).or_default() += 1;
+                }
+
+                let mut strengths = ranks
+                    .iter()
+                    .map(|rank| Ok((copies[rank], self.strength(*rank)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                strengths.sort_by(|a, b| b.cmp(a));
+                strengths
+                    .into_iter()
+                    .map(|(_, strength)| strength)
+                    .collect()
+            }
+        };
+
+        let dealt = cards
+            .iter()
+            .map(|card| self.strength(card.rank))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Evaluation {
+            hand_type,
+            positions,
+            substitutions,
+            tie_break,
+            dealt,
+        })
+    }
+}
+
+/// Every way to pick @k of @n positions, each in increasing order
+fn combinations(n: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut all = vec![];
+    let mut current: Vec<usize> = (0..k).collect();
+
+    loop {
+        all.push(current.clone());
+
+        // VV: Find the rightmost position which can still move right
+        let Some(idx) = (0..k).rev().find(|&idx| current[idx] < n - k + idx) else {
+            return all;
+        };
+        current[idx] += 1;
+        for next in idx + 1..k {
+            current[next] = current[next - 1] + 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evaluation {
+    pub hand_type: HandType,
+    /// The positions of the cards which make up the scored (sub-)hand
+    pub positions: Vec<usize>,
+    /// (wildcard, the card it stands for) for each wildcard of the scored (sub-)hand in the
+    /// order they are dealt
+    pub substitutions: Vec<(Card, Card)>,
+    /// Compared one by one to order hands of the same type
+    pub tie_break: Vec<u8>,
+    /// The strengths of all the cards in the order they are dealt, which order hands whose tie
+    /// breaks are equal e.g. because they have the same groups or the same best sub-hand
+    pub dealt: Vec<u8>,
+}
+
+impl Evaluation {
+    /// Hands are ordered by their type, then by their tie break and then by their dealt cards
+    pub fn key(&self) -> (HandType, &[u8], &[u8]) {
+        (self.hand_type, &self.tie_break, &self.dealt)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Hand {
+    pub cards: String,
+    pub bet: usize,
+    pub evaluation: Evaluation,
+}
+
+pub fn parse_text(text: &str, rules: &Rules) -> Result<Vec<Hand>> {
+    rules.validate()?;
+
+    text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (cards, bet) = line
+                .split_once(' ')
+                .with_context(|| format!("Splitting line {line}"))?;
+            let bet = bet
+                .trim()
+                .parse::<usize>()
+                .with_context(|| format!("Parsing bet {bet}"))?;
+            let evaluation = rules
+                .parse_hand(cards)
+                .and_then(|hand| rules.evaluate(&hand))
+                .with_context(|| format!("Evaluating hand {cards}"))?;
+
+            Ok(Hand {
+                cards: cards.to_string(),
+                bet,
+                evaluation,
+            })
+        })
+        .collect()
+}
+
+pub fn parse_path(path: &std::path::Path, rules: &Rules) -> Result<Vec<Hand>> {
+    let contents = std::fs::read_to_string(path).with_context(|| "Reading input file")?;
+    parse_text(&contents, rules)
+}
+
+/// Sorts @hands from the weakest to the strongest and returns the total winnings
+pub fn solve(hands: &mut [Hand]) -> u128 {
+    hands.sort_by(|a, b| a.evaluation.key().cmp(&b.evaluation.key()));
+
+    hands
+        .iter()
+        .enumerate()
+        .map(|(idx, hand)| hand.bet as u128 * (idx as u128 + 1))
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Preset {
+    /// The rules of part a
+    A,
+    /// The rules of part b, J is a joker
+    B,
+}
+
+/// Command line options which start from a Preset and change parts of it
+#[derive(Debug, clap::Args)]
+pub struct RuleOptions {
+    /// The rules to start from, defaults to the rules of this part
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+
+    /// Every card from the weakest to the strongest e.g. "23456789TJQKA"
+    #[arg(long)]
+    pub ordering: Option<String>,
+
+    /// The cards which stand for whichever card makes the best hand, "" for none
+    #[arg(long)]
+    pub wildcards: Option<String>,
+
+    /// Disable a hand type, hands fall back to the best type they can still make
+    #[arg(long)]
+    pub disable: Vec<HandType>,
+
+    /// Enable a hand type, e.g. straight, flush or straight-flush
+    #[arg(long)]
+    pub enable: Vec<HandType>,
+
+    /// The suits which follow every rank in the input e.g. "shdc", flushes need suits
+    #[arg(long)]
+    pub suits: Option<String>,
+
+    /// Score hands with more cards by their best sub-hand of this many cards
+    #[arg(long)]
+    pub best_of: Option<usize>,
+
+    #[arg(long, value_enum)]
+    pub tie_break: Option<TieBreak>,
+}
+
+impl RuleOptions {
+    pub fn rules(&self, default: Preset) -> Result<Rules> {
+        let mut rules = match self.preset.unwrap_or(default) {
+            Preset::A => Rules::part_a(),
+            Preset::B => Rules::part_b(),
+        };
+
+        if let Some(ordering) = &self.ordering {
+            rules.ordering = ordering.chars().collect();
+        }
+        if let Some(wildcards) = &self.wildcards {
+            rules.wildcards = wildcards.chars().collect();
+        }
+        for hand_type in &self.enable {
+            if !rules.hand_types.contains(hand_type) {
+                rules.hand_types.push(*hand_type);
+            }
+        }
+        rules
+            .hand_types
+            .retain(|hand_type| !self.disable.contains(hand_type));
+        if let Some(suits) = &self.suits {
+            rules.suits = suits.chars().collect();
+        }
+        if self.best_of.is_some() {
+            rules.best_of = self.best_of;
+        }
+        if let Some(tie_break) = self.tie_break {
+            rules.tie_break = tie_break;
+        }
+
+        rules.validate()?;
+        Ok(rules)
+    }
+}
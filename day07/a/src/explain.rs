@@ -0,0 +1,110 @@
+use crate::engine::{Evaluation, Hand};
+
+/// What orders two neighbouring hands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decider {
+    /// They have different types
+    Type,
+    /// The first card of the tie break which differs, from 1. With the Dealt tie break this is
+    /// the position of the card in the (scored) hand.
+    Card(usize),
+    /// The tie breaks are equal and the first dealt card which differs decides, from 1
+    Dealt(usize),
+    /// They are equal and keep the order they are dealt in
+    Tie,
+}
+
+impl Decider {
+    pub fn between(a: &Evaluation, b: &Evaluation) -> Self {
+        if a.hand_type != b.hand_type {
+            return Decider::Type;
+        }
+        if let Some(position) = first_difference(&a.tie_break, &b.tie_break) {
+            return Decider::Card(position);
+        }
+        first_difference(&a.dealt, &b.dealt).map_or(Decider::Tie, Decider::Dealt)
+    }
+}
+
+/// The first position where @a and @b differ, from 1, None if they are equal
+fn first_difference(a: &[u8], b: &[u8]) -> Option<usize> {
+    match a.iter().zip(b).position(|(a, b)| a != b) {
+        Some(idx) => Some(idx + 1),
+        // VV: The shorter one is a prefix of the longer one and sorts first
+        None if a.len() != b.len() => Some(a.len().min(b.len()) + 1),
+        None => None,
+    }
+}
+
+impl std::fmt::Display for Decider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Decider::Type => write!(f, "type"),
+            Decider::Card(position) => write!(f, "card {position}"),
+            Decider::Dealt(position) => write!(f, "dealt card {position}"),
+            Decider::Tie => write!(f, "tie"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub hand: Hand,
+    pub rank: usize,
+    /// What puts the hand above the one ranked just below it
+    pub below: Option<Decider>,
+    /// What puts the hand below the one ranked just above it
+    pub above: Option<Decider>,
+    /// The bet times the rank
+    pub winnings: u128,
+}
+
+impl std::fmt::Display for Explanation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let evaluation = &self.hand.evaluation;
+        write!(
+            f,
+            "{:>5} {} {}",
+            self.rank, self.hand.cards, evaluation.hand_type
+        )?;
+        if !evaluation.substitutions.is_empty() {
+            let substitutions = evaluation
+                .substitutions
+                .iter()
+                .map(|(from, to)| format!("{from}→{to}"))
+                .collect::<Vec<_>>();
+            write!(f, " ({})", substitutions.join(", "))?;
+        }
+        if let Some(below) = self.below {
+            write!(f, ", above {} by {below}", self.rank - 1)?;
+        }
+        if let Some(above) = self.above {
+            write!(f, ", below {} by {above}", self.rank + 1)?;
+        }
+        write!(
+            f,
+            ", wins {} * {} = {}",
+            self.hand.bet, self.rank, self.winnings
+        )
+    }
+}
+
+/// Sorts @hands like solve() and explains the place of each of them, weakest first
+pub fn explain(hands: &mut [Hand]) -> Vec<Explanation> {
+    hands.sort_by(|a, b| a.evaluation.key().cmp(&b.evaluation.key()));
+
+    hands
+        .iter()
+        .enumerate()
+        .map(|(idx, hand)| {
+            let decider = |other: &Hand| Decider::between(&hand.evaluation, &other.evaluation);
+            Explanation {
+                hand: hand.clone(),
+                rank: idx + 1,
+                below: idx.checked_sub(1).map(|below| decider(&hands[below])),
+                above: hands.get(idx + 1).map(decider),
+                winnings: hand.bet as u128 * (idx as u128 + 1),
+            }
+        })
+        .collect()
+}
@@ -1,127 +1,289 @@
-use anyhow::{Context, Result};
-use clap::{arg, command, Parser};
-
-/// First card, is "hand type", followed by the 5 cards in the hand
-/// Hand Type is:
-///     0: High Card
-///     2: One Pair
-///     22: Two pair
-///     30: Three of a kind
-///     32: Full house
-///     40: Four of a kind
-///     60: Five of a kind
-#[derive(Debug)]
-struct Cards {
-    numbers: Vec<u8>,
-}
+use a::analysis::AnalysisOptions;
+use a::engine::{parse_path, solve, Preset, RuleOptions};
+use a::explain::explain;
+use anyhow::Result;
+use clap::Parser;
+
+#[test]
+fn test_sample() -> Result<()> {
+    let sample = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483";
+    let rules = a::engine::Rules::part_a();
+    let mut hands = a::engine::parse_text(sample, &rules)?;
 
-#[derive(Debug)]
-struct Hand {
-    cards: Cards,
-    bet: usize,
+    let solution = solve(&mut hands);
+
+    assert_eq!(solution, 6440);
+
+    Ok(())
 }
 
-fn parse_cards(cards: &str) -> Result<Cards> {
-    let mut histogram = vec![0; 15];
-
-    let mut numbers: Vec<u8> = cards
-        .chars()
-        .map(|c| {
-            let x = match c {
-                '2'..='9' => c.to_digit(10).unwrap(),
-                'T' => 10,
-                'J' => 11,
-                'Q' => 12,
-                'K' => 13,
-                'A' => 14,
-
-                _ => unreachable!("Unexpected Card"),
-            };
-
-            let x = x as u8;
-
-            *histogram.get_mut(x as usize).unwrap() += 1;
-
-            x
-        })
-        .collect();
-
-    // VV: Reverse sort histogram so that larger numbers end up first
-    histogram.sort_by(|a: &u8, &b| b.cmp(a));
-    let histogram = &histogram[..];
-    let hand_type = if histogram[0] == 5 {
-        50u8 // VV: 5 of a kind
-    } else if histogram[0] == 4 {
-        40 // VV: 4 of a kind
-    } else if histogram[0] == 3 && histogram[1] == 2 {
-        32 // VV: full house
-    } else if histogram[0] == 3 {
-        30 // VV: 3 of a kind
-    } else if histogram[0] == 2 && histogram[1] == 2 {
-        22 // VV: 2 pairs
-    } else if histogram[0] == 2 {
-        2 // VV: 1 pair
-    } else {
-        0 // VV: high card
+#[test]
+fn test_rules() -> Result<()> {
+    use a::engine::{HandType, Rules, TieBreak};
+
+    let evaluate = |rules: &Rules, cards: &str| rules.evaluate(&rules.parse_hand(cards)?);
+    let substitutions = |evaluation: &a::engine::Evaluation| {
+        evaluation
+            .substitutions
+            .iter()
+            .map(|(from, to)| format!("{from}{to}"))
+            .collect::<Vec<_>>()
     };
-    numbers.insert(0, hand_type);
+    let part_b = Rules::part_b();
 
-    Ok(Cards { numbers })
-}
+    // VV: Jokers join the largest group, or the strongest card when there is nothing to join
+    let hand = evaluate(&part_b, "KTJJT")?;
+    assert_eq!(hand.hand_type, HandType::FourOfAKind);
+    assert_eq!(substitutions(&hand), ["JT", "JT"]);
+    let hand = evaluate(&part_b, "JJJJJ")?;
+    assert_eq!(hand.hand_type, HandType::FiveOfAKind);
+    assert_eq!(substitutions(&hand), ["JA"; 5]);
+    assert_eq!(evaluate(&part_b, "2345J")?.hand_type, HandType::OnePair);
+    assert_eq!(evaluate(&part_b, "2233J")?.hand_type, HandType::FullHouse);
 
-fn solve(hands: &mut Vec<Hand>) -> u128 {
-    hands.sort_by(|a: &Hand, b| a.cards.numbers.cmp(&b.cards.numbers));
+    // VV: Without four of a kind the joker completes a full house instead
+    let house = Rules {
+        hand_types: HandType::CLASSIC
+            .into_iter()
+            .filter(|t| *t != HandType::FourOfAKind)
+            .collect(),
+        ..Rules::part_b()
+    };
+    let hand = evaluate(&house, "QQQ2J")?;
+    assert_eq!(hand.hand_type, HandType::FullHouse);
+    assert_eq!(substitutions(&hand), ["J2"]);
+    assert_eq!(evaluate(&house, "QQQQ2")?.hand_type, HandType::ThreeOfAKind);
 
-    hands
-        .iter()
-        .enumerate()
-        .map(|(idx, hand)| hand.bet as u128 * (idx as u128 + 1))
-        .sum()
-}
+    // VV: Wildcards the type has no use for must not join a group and promote the hand
+    let without = |disabled: HandType| Rules {
+        hand_types: HandType::CLASSIC
+            .into_iter()
+            .filter(|t| *t != disabled)
+            .collect(),
+        ..Rules::part_b()
+    };
+    let hand = evaluate(&without(HandType::OnePair), "2345J")?;
+    assert_eq!(hand.hand_type, HandType::HighCard);
+    assert_eq!(substitutions(&hand), ["JA"]);
+    let hand = evaluate(&without(HandType::FiveOfAKind), "AAAAJ")?;
+    assert_eq!(hand.hand_type, HandType::FourOfAKind);
+    assert_eq!(substitutions(&hand), ["JK"]);
+    let high_card = Rules {
+        hand_types: vec![HandType::HighCard],
+        ..Rules::part_b()
+    };
+    let hand = evaluate(&high_card, "2JJ45")?;
+    assert_eq!(hand.hand_type, HandType::HighCard);
+    assert_eq!(substitutions(&hand), ["JA", "JK"]);
+
+    // VV: Poker style tie break looks at the largest groups first
+    let grouped = Rules {
+        tie_break: TieBreak::Grouped,
+        ..Rules::part_a()
+    };
+    let low = evaluate(&grouped, "A2233")?;
+    let high = evaluate(&grouped, "4422K")?;
+    assert!(low.key() < high.key());
+    assert!(
+        evaluate(&Rules::part_a(), "A2233")?.key() > evaluate(&Rules::part_a(), "4422K")?.key()
+    );
+
+    // VV: Hands with equal tie breaks still have a total order, by their dealt cards
+    assert_eq!(
+        evaluate(&grouped, "2233A")?.tie_break,
+        evaluate(&grouped, "A2233")?.tie_break
+    );
+    let best_of = Rules {
+        best_of: Some(4),
+        ..Rules::part_a()
+    };
+    assert_eq!(
+        evaluate(&best_of, "AAKQ2")?.tie_break,
+        evaluate(&best_of, "AAKQ3")?.tie_break
+    );
+    for (rules, sample) in [
+        (&grouped, "A2233 1\n2233A 2"),
+        (&grouped, "2233A 2\nA2233 1"),
+        (&best_of, "AAKQ3 1\nAAKQ2 2"),
+        (&best_of, "AAKQ2 2\nAAKQ3 1"),
+    ] {
+        let mut hands = a::engine::parse_text(sample, rules)?;
+        solve(&mut hands);
+        assert_eq!(hands[1].bet, 1);
+    }
+
+    let reversed = Rules {
+        tie_break: TieBreak::Reversed,
+        ..Rules::part_a()
+    };
+    assert!(evaluate(&reversed, "2345A")?.key() > evaluate(&reversed, "A2345")?.key());
+
+    // VV: Several wildcards and a custom ordering
+    let wild = Rules {
+        ordering: "23456789TJQKA*".chars().collect(),
+        wildcards: vec!['*', '2'],
+        ..Rules::part_a()
+    };
+    assert_eq!(evaluate(&wild, "*2KK9")?.hand_type, HandType::FourOfAKind);
+    assert!(evaluate(&wild, "*2KQX").is_err());
 
-fn parse_text(text: &str) -> Result<Vec<Hand>> {
-    Ok(text
-        .trim()
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| line.len() > 0)
-        .map(|line| {
-            let (cards, bet) = line
-                .split_once(' ')
-                .with_context(|| format!("Splitting line {line}"))
-                .unwrap();
-            let cards = parse_cards(cards).unwrap();
-            let bet = bet
-                .parse::<usize>()
-                .with_context(|| "Parsing bet {bet}")
-                .unwrap();
-
-            Hand { cards, bet }
-        })
-        .collect())
+    assert!(Rules {
+        hand_types: vec![HandType::OnePair],
+        ..Rules::part_a()
+    }
+    .validate()
+    .is_err());
+    assert!(Rules {
+        wildcards: vec!['*'],
+        ..Rules::part_a()
+    }
+    .validate()
+    .is_err());
+    // VV: Strengths are bytes, the 257th card of an ordering would wrap around to 0
+    let long = Rules {
+        ordering: (0..257)
+            .filter_map(|idx| char::from_u32(0x100 + idx))
+            .collect(),
+        ..Rules::part_a()
+    };
+    assert!(long.validate().is_err());
+    assert!(long.strength('\u{200}').is_err());
+    assert_eq!(long.strength('\u{1ff}')?, 255);
+    assert!(Rules {
+        ordering: "22".chars().collect(),
+        ..Rules::part_a()
+    }
+    .validate()
+    .is_err());
+
+    Ok(())
 }
 
-fn parse_path(path: &std::path::Path) -> Result<Vec<Hand>> {
-    let contents = std::fs::read_to_string(path).with_context(|| "Reading input file")?;
-    parse_text(&contents)
+#[test]
+fn test_suits_and_best_of() -> Result<()> {
+    use a::engine::{HandType, Rules};
+
+    let poker = Rules {
+        ordering: "23456789TJQKA".chars().collect(),
+        hand_types: HandType::ALL.to_vec(),
+        suits: "shdc".chars().collect(),
+        best_of: Some(5),
+        ..Rules::part_a()
+    };
+    let evaluate = |rules: &Rules, cards: &str| rules.evaluate(&rules.parse_hand(cards)?);
+
+    assert_eq!(
+        evaluate(&poker, "9s8hTd7cJs")?.hand_type,
+        HandType::Straight
+    );
+    assert_eq!(evaluate(&poker, "2s8s4s9sKs")?.hand_type, HandType::Flush);
+    assert_eq!(
+        evaluate(&poker, "2s8s4s9sKh")?.hand_type,
+        HandType::HighCard
+    );
+    assert_eq!(
+        evaluate(&poker, "9s8sTs7sJs")?.hand_type,
+        HandType::StraightFlush
+    );
+    assert_eq!(
+        evaluate(&poker, "9s8sTs7sJh")?.hand_type,
+        HandType::Straight
+    );
+
+    // VV: 8 cards score their best 5, the 2s and the 3 are left out of the straight flush
+    let hand = evaluate(&poker, "2h9s8s2dTs7s3cJs")?;
+    assert_eq!(hand.hand_type, HandType::StraightFlush);
+    assert_eq!(hand.positions, [1, 2, 4, 5, 7]);
+    let hand = evaluate(&poker, "KhKdKs2c2h9d")?;
+    assert_eq!(hand.hand_type, HandType::FullHouse);
+
+    // VV: A joker fills the gap in the straight flush and takes the suit of the flush
+    let wild = Rules {
+        ordering: "*23456789TJQKA".chars().collect(),
+        wildcards: vec!['*'],
+        ..poker.clone()
+    };
+    let hand = evaluate(&wild, "9h8h*dJh7h")?;
+    assert_eq!(hand.hand_type, HandType::StraightFlush);
+    assert_eq!(
+        hand.substitutions
+            .iter()
+            .map(|(from, to)| format!("{from}{to}"))
+            .collect::<Vec<_>>(),
+        ["*dTh"]
+    );
+    assert_eq!(
+        evaluate(&wild, "*s*s*s*s*s")?.hand_type,
+        HandType::FiveOfAKind
+    );
+
+    // VV: Hands of any length without best-of still give a total order
+    let unsuited = Rules::part_a();
+    let mut hands = a::engine::parse_text("AAA 1\n22222K2 2\nKK 3\n23456789 4", &unsuited)?;
+    assert_eq!(solve(&mut hands), 4 + 2 * 3 + 3 + 4 * 2);
+
+    assert!(poker.parse_hand("9s8").is_err());
+    assert!(poker.parse_hand("9x").is_err());
+    assert!(Rules {
+        suits: vec![],
+        ..poker.clone()
+    }
+    .validate()
+    .is_err());
+
+    Ok(())
 }
 
 #[test]
-fn test_sample() -> Result<()> {
+fn test_analysis() -> Result<()> {
+    use a::analysis::{analyse_hands, distribution, Deck};
+    use a::engine::{HandType, Rules};
+
+    // VV: Completing KKKQ: the last K, one of 3 Qs, or any of the other 44 cards
+    let part_a = Rules::part_a();
+    let partial = part_a.parse_hand("KKKQ")?;
+    let mut deck = Deck::full(&part_a, 4);
+    deck.remove(&partial)?;
+    let odds = distribution(&part_a, &partial, &deck, 1)?;
+    assert_eq!(odds.total, 48);
+    assert_eq!(odds.ways[&HandType::FourOfAKind], 1);
+    assert_eq!(odds.ways[&HandType::FullHouse], 3);
+    assert_eq!(odds.ways[&HandType::ThreeOfAKind], 44);
+
+    // VV: Drawing one of the 4 jokers also makes four of a kind
+    let part_b = Rules::part_b();
+    let odds = distribution(&part_b, &partial, &deck, 1)?;
+    assert_eq!(odds.ways[&HandType::FourOfAKind], 5);
+    assert_eq!(odds.probability(HandType::ThreeOfAKind), 40.0 / 48.0);
+
+    let odds = distribution(&part_b, &partial, &deck, 2)?;
+    assert_eq!(odds.total, 48 * 47 / 2);
+    assert!(distribution(&part_b, &partial, &deck, 49).is_err());
+    assert!(deck.remove(&part_b.parse_hand("KK")?).is_err());
+
     let sample = "32T3K 765
 T55J5 684
 KK677 28
 KTJJT 220
 QQQJA 483";
-    let mut cards = parse_text(sample)?;
-
-    println!("Cards: {cards:#?}");
-
-    let solution = solve(&mut cards);
-
-    println!("Sorted Cards: {cards:#?}");
-
-    assert_eq!(solution, 6440);
+    let reports = analyse_hands(sample, &part_b, 4)?;
+    assert_eq!(
+        reports.iter().map(|r| r.rank).collect::<Vec<_>>(),
+        [1, 3, 2, 5, 4]
+    );
+    assert_eq!(
+        reports.iter().map(|r| r.moves()).collect::<Vec<_>>(),
+        [0, 1, 1, -3, 1]
+    );
+    // VV: KTT keeps at least its pair of tens when both jokers are drawn again
+    let redrawn = &reports[3].distribution;
+    assert_eq!(redrawn.total, 49 * 48 / 2);
+    assert!(redrawn.ways.keys().all(|t| *t >= HandType::OnePair));
+    assert_eq!(reports[0].distribution.ways.len(), 1);
 
     Ok(())
 }
@@ -131,13 +293,32 @@ QQQJA 483";
 struct Args {
     #[arg(short, long, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    #[command(flatten)]
+    rules: RuleOptions,
+
+    #[command(flatten)]
+    analysis: AnalysisOptions,
+
+    /// Print every hand from the weakest to the strongest with why it got its rank
+    #[arg(long)]
+    explain: bool,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
     let path = std::env::current_dir().unwrap().join(args.input);
-    let mut cards = parse_path(&path)?;
-    let solution = solve(&mut cards);
+    let rules = args.rules.rules(Preset::A)?;
+    if args.analysis.run(&rules, &path)? {
+        return Ok(());
+    }
+    let mut hands = parse_path(&path, &rules)?;
+    if args.explain {
+        for explanation in explain(&mut hands) {
+            println!("{explanation}");
+        }
+    }
+    let solution = solve(&mut hands);
 
     println!("{solution}");
 
@@ -0,0 +1,6 @@
+#[path = "../../a/src/analysis.rs"]
+pub mod analysis;
+#[path = "../../a/src/engine.rs"]
+pub mod engine;
+#[path = "../../a/src/explain.rs"]
+pub mod explain;
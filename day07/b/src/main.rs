@@ -1,139 +1,8 @@
-use anyhow::{Context, Result};
-use clap::{arg, command, Parser};
-
-const HIGH: u8 = 0;
-const PAIR: u8 = 2;
-const TWO_PAIR: u8 = 22;
-const THREE_OF_A_KIND: u8 = 30;
-const FULL_HOUSE: u8 = 32;
-const FOUR_OF_A_KIND: u8 = 40;
-const FIVE_OF_A_KIND: u8 = 50;
-
-/// First card, is "hand type", followed by the 5 cards in the hand
-/// Hand Type is:
-///     0: High Card
-///     2: One Pair
-///     22: Two pair
-///     30: Three of a kind
-///     32: Full house
-///     40: Four of a kind
-///     50: Five of a kind
-#[derive(Debug)]
-struct Cards {
-    numbers: Vec<u8>,
-}
-
-#[derive(Debug)]
-struct Hand {
-    cards: Cards,
-    bet: usize,
-}
-
-fn parse_cards(cards: &str) -> Result<Cards> {
-    let mut histogram = vec![0u8; 15];
-
-    let mut numbers: Vec<u8> = cards
-        .chars()
-        .map(|c| {
-            let x = match c {
-                '2'..='9' => c.to_digit(10).unwrap(),
-                'T' => 10,
-                'J' => 1,
-                'Q' => 12,
-                'K' => 13,
-                'A' => 14,
-
-                _ => unreachable!("Unexpected Card"),
-            };
-
-            let x = x as u8;
-
-            *histogram.get_mut(x as usize).unwrap() += 1;
-
-            x
-        })
-        .collect();
-
-    // VV: Each entry is (card type, card population)
-    let mut histogram: Vec<(usize, u8)> = histogram
-        .iter()
-        .enumerate()
-        .map(|(idx, card)| (idx, *card))
-        .collect();
-
-    // VV: Extract the Jokers, then find the card with the most copies and increase it
-    // by the number of Jokers
-    let jokers = histogram[1].1;
-    histogram[1].1 = 0;
-
-    // VV: Reverse sort histogram so that larger numbers end up first
-    histogram.sort_by(|a: &(usize, u8), &b| b.1.cmp(&a.1));
-
-    // VV: update histogram with Joker info before looking at the cards
-    histogram[0].1 += jokers;
-
-    // VV: re-sort to take into account changes introduced by converting Jokers to other cards
-    histogram.sort_by(|a: &(usize, u8), &b| b.1.cmp(&a.1));
-
-    // VV: The Jokers have already been changed to the most beneficial Card.
-    // Therefore, there's no need for special treatment here.
-    let hand_type = if histogram[0].1 == 5 {
-        FIVE_OF_A_KIND
-    } else if histogram[0].1 == 4 {
-        FOUR_OF_A_KIND
-    } else if histogram[0].1 == 3 && histogram[1].1 == 2 {
-        FULL_HOUSE
-    } else if histogram[0].1 == 3 {
-        THREE_OF_A_KIND
-    } else if histogram[0].1 == 2 && histogram[1].1 == 2 {
-        TWO_PAIR
-    } else if histogram[0].1 == 2 {
-        PAIR
-    } else {
-        HIGH
-    };
-
-    numbers.insert(0, hand_type);
-
-    Ok(Cards { numbers })
-}
-
-fn solve(hands: &mut Vec<Hand>) -> u128 {
-    hands.sort_by(|a: &Hand, b| a.cards.numbers.cmp(&b.cards.numbers));
-
-    hands
-        .iter()
-        .enumerate()
-        .map(|(idx, hand)| hand.bet as u128 * (idx as u128 + 1))
-        .sum()
-}
-
-fn parse_text(text: &str) -> Result<Vec<Hand>> {
-    Ok(text
-        .trim()
-        .lines()
-        .map(|line| line.trim())
-        .filter(|line| line.len() > 0)
-        .map(|line| {
-            let (cards, bet) = line
-                .split_once(' ')
-                .with_context(|| format!("Splitting line {line}"))
-                .unwrap();
-            let cards = parse_cards(cards).unwrap();
-            let bet = bet
-                .parse::<usize>()
-                .with_context(|| "Parsing bet {bet}")
-                .unwrap();
-
-            Hand { cards, bet }
-        })
-        .collect())
-}
-
-fn parse_path(path: &std::path::Path) -> Result<Vec<Hand>> {
-    let contents = std::fs::read_to_string(path).with_context(|| "Reading input file")?;
-    parse_text(&contents)
-}
+use anyhow::Result;
+use b::analysis::AnalysisOptions;
+use b::engine::{parse_path, solve, Preset, RuleOptions};
+use b::explain::explain;
+use clap::Parser;
 
 #[test]
 fn test_sample() -> Result<()> {
@@ -142,15 +11,89 @@ T55J5 684
 KK677 28
 KTJJT 220
 QQQJA 483";
-    let mut cards = parse_text(sample)?;
+    let rules = b::engine::Rules::part_b();
+    let mut hands = b::engine::parse_text(sample, &rules)?;
 
-    println!("Cards: {cards:#?}");
+    let solution = solve(&mut hands);
 
-    let solution = solve(&mut cards);
+    assert_eq!(solution, 5905);
 
-    println!("Sorted Cards: {cards:#?}");
+    Ok(())
+}
 
-    assert_eq!(solution, 5905);
+#[test]
+fn test_explain() -> Result<()> {
+    use b::explain::{explain, Decider};
+
+    let sample = "32T3K 765
+T55J5 684
+KK677 28
+KTJJT 220
+QQQJA 483
+QQQJA 1";
+    let mut hands = b::engine::parse_text(sample, &b::engine::Rules::part_b())?;
+    let explanations = explain(&mut hands);
+
+    assert_eq!(
+        explanations
+            .iter()
+            .map(|e| e.hand.cards.as_str())
+            .collect::<Vec<_>>(),
+        ["32T3K", "KK677", "T55J5", "QQQJA", "QQQJA", "KTJJT"]
+    );
+    assert_eq!(
+        explanations.iter().map(|e| e.above).collect::<Vec<_>>(),
+        [
+            Some(Decider::Type),
+            Some(Decider::Type),
+            Some(Decider::Card(1)),
+            Some(Decider::Tie),
+            Some(Decider::Card(1)),
+            None
+        ]
+    );
+    assert_eq!(explanations[0].below, None);
+    assert_eq!(
+        explanations[5].to_string(),
+        "    6 KTJJT four-of-a-kind (J→T, J→T), above 5 by card 1, wins 220 * 6 = 1320"
+    );
+    assert_eq!(
+        explanations.iter().map(|e| e.winnings).sum::<u128>(),
+        solve(&mut hands)
+    );
+
+    // VV: A tie break which is a prefix of the other is decided by the first card past its end
+    let evaluation = |tie_break: &[u8]| b::engine::Evaluation {
+        hand_type: b::engine::HandType::HighCard,
+        positions: vec![],
+        substitutions: vec![],
+        tie_break: tie_break.to_vec(),
+        dealt: vec![],
+    };
+    assert_eq!(
+        Decider::between(&evaluation(&[3, 1]), &evaluation(&[3, 1, 2])),
+        Decider::Card(3)
+    );
+    assert_eq!(
+        Decider::between(&evaluation(&[3, 1, 2]), &evaluation(&[3, 1])),
+        Decider::Card(3)
+    );
+    assert_eq!(
+        Decider::between(&evaluation(&[3, 1]), &evaluation(&[3, 1])),
+        Decider::Tie
+    );
+
+    // VV: Grouped tie breaks are equal for the same cards, the dealt order decides between them
+    let grouped = b::engine::Rules {
+        tie_break: b::engine::TieBreak::Grouped,
+        ..b::engine::Rules::part_a()
+    };
+    let evaluate = |cards: &str| grouped.evaluate(&grouped.parse_hand(cards)?);
+    assert_eq!(
+        Decider::between(&evaluate("A2233")?, &evaluate("2A233")?),
+        Decider::Dealt(1)
+    );
+    assert_eq!(Decider::Dealt(1).to_string(), "dealt card 1");
 
     Ok(())
 }
@@ -160,13 +103,32 @@ QQQJA 483";
 struct Args {
     #[arg(short, long, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    #[command(flatten)]
+    rules: RuleOptions,
+
+    #[command(flatten)]
+    analysis: AnalysisOptions,
+
+    /// Print every hand from the weakest to the strongest with why it got its rank
+    #[arg(long)]
+    explain: bool,
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
     let path = std::env::current_dir().unwrap().join(args.input);
-    let mut cards = parse_path(&path)?;
-    let solution = solve(&mut cards);
+    let rules = args.rules.rules(Preset::B)?;
+    if args.analysis.run(&rules, &path)? {
+        return Ok(());
+    }
+    let mut hands = parse_path(&path, &rules)?;
+    if args.explain {
+        for explanation in explain(&mut hands) {
+            println!("{explanation}");
+        }
+    }
+    let solution = solve(&mut hands);
 
     println!("{solution}");
 
@@ -0,0 +1,278 @@
+use crate::ghost::trace;
+use crate::network::Board;
+use crate::simulator::Selector;
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    Dot,
+    Graphml,
+}
+
+const START_COLOR: &str = "palegreen";
+const END_COLOR: &str = "lightcoral";
+/// The outline of the nodes on the cycle of each walker, reused when there are more walkers
+const CYCLE_COLORS: [&str; 6] = [
+    "blue",
+    "darkorange",
+    "purple",
+    "teal",
+    "goldenrod",
+    "magenta",
+];
+
+/// The maze with what is known about its walkers
+#[derive(Debug, Clone)]
+pub struct Graph<'a> {
+    board: &'a Board,
+    /// The start node of each walker
+    pub starts: Vec<&'a str>,
+    pub ends: BTreeSet<&'a str>,
+    /// The nodes each walker visits once it loops
+    pub cycles: Vec<BTreeSet<&'a str>>,
+    /// The nodes reachable from the start of each walker, without those of earlier walkers
+    /// because a node can only be in one cluster
+    pub clusters: Option<Vec<BTreeSet<&'a str>>>,
+}
+
+impl<'a> Graph<'a> {
+    pub fn new(board: &'a Board, start: &Selector, end: &Selector, clusters: bool) -> Result<Self> {
+        let mut starts = board
+            .maze
+            .keys()
+            .map(|node| node.as_str())
+            .filter(|node| start.matches(node))
+            .collect::<Vec<_>>();
+        starts.sort();
+        if starts.is_empty() {
+            bail!("There are no start nodes");
+        }
+
+        let ends = board
+            .maze
+            .keys()
+            .map(|node| node.as_str())
+            .filter(|node| end.matches(node))
+            .collect();
+
+        let cycles = starts
+            .iter()
+            .map(|start| {
+                let (path, cycle_start) = trace(board, start)?;
+                Ok(path[cycle_start..].iter().copied().collect())
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let clusters = clusters.then(|| {
+            let mut placed = BTreeSet::new();
+            starts
+                .iter()
+                .map(|start| {
+                    let mut cluster = BTreeSet::new();
+                    let mut pending = vec![*start];
+                    while let Some(node) = pending.pop() {
+                        if !placed.insert(node) {
+                            continue;
+                        }
+                        cluster.insert(node);
+                        if let Some((left, right)) = board.maze.get(node) {
+                            pending.extend([left.as_str(), right.as_str()]);
+                        }
+                    }
+                    cluster
+                })
+                .collect()
+        });
+
+        Ok(Self {
+            board,
+            starts,
+            ends,
+            cycles,
+            clusters,
+        })
+    }
+
+    /// Every node of the maze, including those which are only the target of an edge
+    fn nodes(&self) -> BTreeSet<&'a str> {
+        self.board
+            .maze
+            .iter()
+            .flat_map(|(node, (left, right))| [node.as_str(), left.as_str(), right.as_str()])
+            .collect()
+    }
+
+    /// (from, to, label) with one edge labelled LR when both directions lead to the same node
+    fn edges(&self) -> Vec<(&'a str, &'a str, &'static str)> {
+        let mut edges = self
+            .board
+            .maze
+            .iter()
+            .flat_map(|(node, (left, right))| {
+                if left == right {
+                    vec![(node.as_str(), left.as_str(), "LR")]
+                } else {
+                    vec![
+                        (node.as_str(), left.as_str(), "L"),
+                        (node.as_str(), right.as_str(), "R"),
+                    ]
+                }
+            })
+            .collect::<Vec<_>>();
+        edges.sort();
+        edges
+    }
+
+    /// The start nodes of the walkers whose cycle goes through @node
+    fn cycled_by(&self, node: &str) -> Vec<&'a str> {
+        self.starts
+            .iter()
+            .zip(&self.cycles)
+            .filter(|(_, cycle)| cycle.contains(node))
+            .map(|(start, _)| *start)
+            .collect()
+    }
+
+    fn fill(&self, node: &str) -> Option<&'static str> {
+        if self.starts.contains(&node) {
+            Some(START_COLOR)
+        } else if self.ends.contains(node) {
+            Some(END_COLOR)
+        } else {
+            None
+        }
+    }
+
+    fn outline(&self, node: &str) -> Option<&'static str> {
+        self.cycles
+            .iter()
+            .position(|cycle| cycle.contains(node))
+            .map(|walker| CYCLE_COLORS[walker % CYCLE_COLORS.len()])
+    }
+
+    pub fn write_dot(&self, out: &mut impl Write) -> std::fmt::Result {
+        writeln!(out, "digraph network {{")?;
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+
+        for (idx, cluster) in self.clusters.iter().flatten().enumerate() {
+            writeln!(out, "    subgraph cluster_{idx} {{")?;
+            writeln!(out, "        label={};", quote(self.starts[idx]))?;
+            for node in cluster {
+                writeln!(out, "        {};", quote(node))?;
+            }
+            writeln!(out, "    }}")?;
+        }
+
+        for node in self.nodes() {
+            let mut attributes = vec![];
+            if let Some(fill) = self.fill(node) {
+                attributes.push(format!("style=filled, fillcolor={fill}"));
+            }
+            if let Some(outline) = self.outline(node) {
+                let walkers = self.cycled_by(node).join(", ");
+                attributes.push(format!(
+                    "color={outline}, penwidth=3, tooltip={}",
+                    quote(&format!("cycle of {walkers}"))
+                ));
+            }
+            if !attributes.is_empty() {
+                writeln!(out, "    {} [{}];", quote(node), attributes.join(", "))?;
+            }
+        }
+
+        for (from, to, label) in self.edges() {
+            writeln!(out, "    {} -> {} [label={label}];", quote(from), quote(to))?;
+        }
+
+        writeln!(out, "}}")
+    }
+
+    pub fn write_graphml(&self, out: &mut impl Write) -> std::fmt::Result {
+        let escape = |text: &str| {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+
+        write!(
+            out,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">
+  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>
+  <key id=\"fill\" for=\"node\" attr.name=\"fill\" attr.type=\"string\"/>
+  <key id=\"outline\" for=\"node\" attr.name=\"outline\" attr.type=\"string\"/>
+  <key id=\"cycles\" for=\"node\" attr.name=\"cycles\" attr.type=\"string\"/>
+  <key id=\"direction\" for=\"edge\" attr.name=\"direction\" attr.type=\"string\"/>
+  <graph id=\"network\" edgedefault=\"directed\">
+"
+        )?;
+
+        let node = |out: &mut dyn Write, node: &str, indent: &str| -> std::fmt::Result {
+            let kind = match (self.starts.contains(&node), self.ends.contains(node)) {
+                (true, true) => "start end",
+                (true, false) => "start",
+                (false, true) => "end",
+                (false, false) => "node",
+            };
+            writeln!(out, "{indent}<node id=\"{}\">", escape(node))?;
+            writeln!(out, "{indent}  <data key=\"kind\">{kind}</data>")?;
+            if let Some(fill) = self.fill(node) {
+                writeln!(out, "{indent}  <data key=\"fill\">{fill}</data>")?;
+            }
+            if let Some(outline) = self.outline(node) {
+                let walkers = escape(&self.cycled_by(node).join(","));
+                writeln!(out, "{indent}  <data key=\"outline\">{outline}</data>")?;
+                writeln!(out, "{indent}  <data key=\"cycles\">{walkers}</data>")?;
+            }
+            writeln!(out, "{indent}</node>")
+        };
+
+        // VV: Clusters are nested graphs inside a node of their own
+        let mut clustered = BTreeMap::new();
+        for (idx, cluster) in self.clusters.iter().flatten().enumerate() {
+            writeln!(out, "    <node id=\"cluster_{idx}\">")?;
+            writeln!(
+                out,
+                "      <graph id=\"cluster_{idx}:\" edgedefault=\"directed\">"
+            )?;
+            for member in cluster {
+                node(out, member, "        ")?;
+                clustered.insert(*member, idx);
+            }
+            writeln!(out, "      </graph>")?;
+            writeln!(out, "    </node>")?;
+        }
+
+        for member in self.nodes() {
+            if !clustered.contains_key(member) {
+                node(out, member, "    ")?;
+            }
+        }
+
+        for (idx, (from, to, label)) in self.edges().into_iter().enumerate() {
+            writeln!(
+                out,
+                "    <edge id=\"e{idx}\" source=\"{}\" target=\"{}\">",
+                escape(from),
+                escape(to)
+            )?;
+            writeln!(out, "      <data key=\"direction\">{label}</data>")?;
+            writeln!(out, "    </edge>")?;
+        }
+
+        writeln!(out, "  </graph>\n</graphml>")
+    }
+
+    pub fn export(&self, format: Format) -> String {
+        let mut out = String::new();
+        // VV: Writing to a String cannot fail
+        let _ = match format {
+            Format::Dot => self.write_dot(&mut out),
+            Format::Graphml => self.write_graphml(&mut out),
+        };
+        out
+    }
+}
@@ -0,0 +1,185 @@
+use crate::network::Board;
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeSet, HashMap};
+
+/// The steps at which a walker stands on an end node. The walker eventually loops because
+/// its state (node, index of the next direction) can only take finitely many values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Walk {
+    /// The end node hits before the walker enters its cycle
+    pub prefix: Vec<u128>,
+    /// The step at which the walker enters its cycle
+    pub start: u128,
+    /// How many steps one trip around the cycle takes
+    pub length: u128,
+    /// The end node hits in [start, start + length), each repeats every @length steps
+    pub offsets: Vec<u128>,
+}
+
+/// The node of a walker that starts on @start at every step until its state repeats, and the
+/// step at which the repeated state was first seen i.e. the start of the cycle
+pub fn trace<'a>(board: &'a Board, start: &'a str) -> Result<(Vec<&'a str>, usize)> {
+    let mut seen: HashMap<(&str, usize), usize> = HashMap::new();
+    let mut path = vec![];
+    let mut curr = start;
+
+    loop {
+        let step = path.len();
+        let state = (curr, board.direction_index(step as u128));
+        if let Some(first) = seen.get(&state) {
+            return Ok((path, *first));
+        }
+        seen.insert(state, step);
+        path.push(curr);
+
+        curr = board
+            .next(curr, step as u128)
+            .with_context(|| format!("Walking from {start}"))?;
+    }
+}
+
+impl Walk {
+    /// Walks from @start until the walker loops, @is_end tells which nodes are end nodes
+    pub fn new(board: &Board, start: &str, is_end: impl Fn(&str) -> bool) -> Result<Self> {
+        let (path, cycle_start) = trace(board, start)?;
+        let (prefix, offsets) = (0..path.len() as u128)
+            .filter(|step| is_end(path[*step as usize]))
+            .partition(|hit| *hit < cycle_start as u128);
+
+        Ok(Self {
+            prefix,
+            start: cycle_start as u128,
+            length: (path.len() - cycle_start) as u128,
+            offsets,
+        })
+    }
+
+    /// Whether the walker stands on an end node at @step
+    pub fn hits(&self, step: u128) -> bool {
+        if step < self.start {
+            self.prefix.contains(&step)
+        } else {
+            let offset = self.start + (step - self.start) % self.length;
+            self.offsets.contains(&offset)
+        }
+    }
+}
+
+/// Returns (g, x, y) such that a*x + b*y = g = gcd(a, b)
+fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = extended_gcd(b, a % b);
+        (g, y, x - (a / b) * y)
+    }
+}
+
+/// The steps which are @r1 mod @m1 and @r2 mod @m2, as (r, m) i.e. r mod m. None if there are
+/// none. The moduli need not be coprime (generalised Chinese Remainder Theorem).
+pub fn combine((r1, m1): (u128, u128), (r2, m2): (u128, u128)) -> Result<Option<(u128, u128)>> {
+    let overflow = || format!("Combining {r1} mod {m1} with {r2} mod {m2} overflows");
+    let to_signed = |x: u128| i128::try_from(x).with_context(overflow);
+    let (r1, m1, r2, m2) = (
+        to_signed(r1)?,
+        to_signed(m1)?,
+        to_signed(r2)?,
+        to_signed(m2)?,
+    );
+
+    let (g, x, _) = extended_gcd(m1, m2);
+    let difference = r2 - r1;
+    if difference % g != 0 {
+        return Ok(None);
+    }
+
+    let lcm = (m1 / g).checked_mul(m2).with_context(overflow)?;
+    // VV: m1 * x = g mod m2, so r1 + m1 * x * difference / g is r2 mod m2
+    let k = (x % (m2 / g))
+        .checked_mul(difference / g)
+        .with_context(overflow)?
+        .rem_euclid(m2 / g);
+    let r = (r1 + m1.checked_mul(k).with_context(overflow)?).rem_euclid(lcm);
+
+    Ok(Some((r as u128, lcm as u128)))
+}
+
+/// The earliest step at which every walker stands on an end node, None if that never happens
+pub fn synchronise(walks: &[Walk]) -> Result<Option<u128>> {
+    // VV: Before the last walker enters its cycle every common hit is in the prefix of that walker
+    let latest_start = walks.iter().map(|walk| walk.start).max().unwrap_or(0);
+    let early = walks
+        .iter()
+        .flat_map(|walk| walk.prefix.iter().copied())
+        .filter(|step| walks.iter().all(|walk| walk.hits(*step)))
+        .min();
+    if early.is_some() {
+        return Ok(early);
+    }
+
+    // VV: From then on every walker repeats its offsets, keep all residue classes that agree
+    let mut classes: BTreeSet<(u128, u128)> = BTreeSet::from([(0, 1)]);
+    for walk in walks {
+        let mut next = BTreeSet::new();
+        for class in &classes {
+            for offset in &walk.offsets {
+                if let Some(combined) = combine(*class, (offset % walk.length, walk.length))? {
+                    next.insert(combined);
+                }
+            }
+        }
+        classes = next;
+    }
+
+    let steps = classes
+        .into_iter()
+        .map(|(r, m)| {
+            if r >= latest_start {
+                return Ok(r);
+            }
+            // VV: The first step of the class at or after latest_start
+            (latest_start - r)
+                .div_ceil(m)
+                .checked_mul(m)
+                .and_then(|x| x.checked_add(r))
+                .context("The first synchronised step overflows")
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(steps.into_iter().min())
+}
+
+/// The earliest step at which every walker that starts on a node ending in A stands on a node
+/// ending in Z
+pub fn solve(board: &Board) -> Result<Option<u128>> {
+    solve_with(
+        board,
+        |node| node.ends_with('A'),
+        |node| node.ends_with('Z'),
+    )
+}
+
+/// The earliest step at which every walker that starts on a node matching @is_start stands on a
+/// node matching @is_end
+pub fn solve_with(
+    board: &Board,
+    is_start: impl Fn(&str) -> bool,
+    is_end: impl Fn(&str) -> bool,
+) -> Result<Option<u128>> {
+    let mut starts = board
+        .maze
+        .keys()
+        .filter(|node| is_start(node))
+        .collect::<Vec<_>>();
+    starts.sort();
+    if starts.is_empty() {
+        bail!("There are no start nodes");
+    }
+
+    let walks = starts
+        .into_iter()
+        .map(|start| Walk::new(board, start, &is_end))
+        .collect::<Result<Vec<_>>>()?;
+
+    synchronise(&walks)
+}
@@ -0,0 +1,4 @@
+pub mod export;
+pub mod ghost;
+pub mod network;
+pub mod simulator;
@@ -0,0 +1,91 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+#[derive(Debug)]
+pub struct Board {
+    /// node -> (left, right)
+    pub maze: HashMap<String, (String, String)>,
+    pub directions: Vec<Direction>,
+}
+
+impl Board {
+    /// The index of the direction followed at @step, directions repeat forever
+    pub fn direction_index(&self, step: u128) -> usize {
+        (step % self.directions.len() as u128) as usize
+    }
+
+    /// Where a walker on @node goes at @step
+    pub fn next(&self, node: &str, step: u128) -> Result<&str> {
+        let (left, right) = self
+            .maze
+            .get(node)
+            .with_context(|| format!("Node {node} is not in the maze"))?;
+
+        Ok(match self.directions[self.direction_index(step)] {
+            Direction::Left => left,
+            Direction::Right => right,
+        })
+    }
+}
+
+pub fn parse_text(text: &str) -> Result<Board> {
+    let mut lines = text.lines();
+
+    let directions = lines
+        .next()
+        .with_context(|| "Extracting directions")?
+        .trim()
+        .chars()
+        .map(|c| match c {
+            'L' => Ok(Direction::Left),
+            'R' => Ok(Direction::Right),
+            c => bail!("Unexpected direction {c}"),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if directions.is_empty() {
+        bail!("There are no directions");
+    }
+
+    let mut maze = HashMap::new();
+
+    for line in lines.map(|x| x.trim()).filter(|x| !x.is_empty()) {
+        let (curr, next) = line
+            .split_once('=')
+            .with_context(|| format!("Splitting maze line {line} on ="))?;
+        let curr = curr.trim();
+        let (left, right) = next
+            .trim()
+            .split_once(',')
+            .with_context(|| format!("Splitting next nodes of {curr}"))?;
+
+        let left = left
+            .trim()
+            .strip_prefix('(')
+            .with_context(|| format!("Stripping prefix in left node of {curr}"))?;
+        let right = right
+            .trim()
+            .strip_suffix(')')
+            .with_context(|| format!("Stripping suffix in right node of {curr}"))?;
+
+        if maze
+            .insert(curr.to_string(), (left.to_string(), right.to_string()))
+            .is_some()
+        {
+            bail!("Node {curr} is defined twice");
+        }
+    }
+
+    Ok(Board { directions, maze })
+}
+
+pub fn parse_path(path: &std::path::Path) -> Result<Board> {
+    let contents = std::fs::read_to_string(path).with_context(|| "Reading file")?;
+
+    parse_text(&contents)
+}
@@ -0,0 +1,198 @@
+use crate::export::{Format, Graph};
+use crate::ghost::solve_with;
+use crate::network::Board;
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use std::collections::HashSet;
+
+/// Picks start or end nodes by name
+#[derive(Debug, Clone)]
+pub enum Selector {
+    Pattern(Regex),
+    Names(HashSet<String>),
+}
+
+impl Selector {
+    pub fn matches(&self, node: &str) -> bool {
+        match self {
+            Selector::Pattern(pattern) => pattern.is_match(node),
+            Selector::Names(names) => names.contains(node),
+        }
+    }
+}
+
+impl std::str::FromStr for Selector {
+    type Err = anyhow::Error;
+
+    /// Either names:NAME,NAME,... or a regex e.g. "A$"
+    fn from_str(text: &str) -> Result<Self> {
+        Ok(match text.strip_prefix("names:") {
+            Some(names) => {
+                let names = names
+                    .split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .collect::<HashSet<_>>();
+                if names.is_empty() {
+                    bail!("There are no names in {text}");
+                }
+                Selector::Names(names)
+            }
+            None => Selector::Pattern(
+                Regex::new(text).with_context(|| format!("Invalid node pattern {text}"))?,
+            ),
+        })
+    }
+}
+
+/// Moves all walkers one instruction at a time
+#[derive(Debug, Clone)]
+pub struct Simulator<'a> {
+    board: &'a Board,
+    end: Selector,
+    /// How many instructions the walkers have followed
+    pub step: u128,
+    /// The node of each walker, in the order of the names of their start nodes
+    pub walkers: Vec<&'a str>,
+}
+
+impl<'a> Simulator<'a> {
+    pub fn new(board: &'a Board, start: &Selector, end: Selector) -> Result<Self> {
+        let mut walkers = board
+            .maze
+            .keys()
+            .map(|node| node.as_str())
+            .filter(|node| start.matches(node))
+            .collect::<Vec<_>>();
+        walkers.sort();
+        if walkers.is_empty() {
+            bail!("There are no start nodes");
+        }
+
+        Ok(Self {
+            board,
+            end,
+            step: 0,
+            walkers,
+        })
+    }
+
+    /// Follows the next instruction
+    pub fn advance(&mut self) -> Result<()> {
+        for walker in self.walkers.iter_mut() {
+            *walker = self.board.next(walker, self.step)?;
+        }
+        self.step += 1;
+        Ok(())
+    }
+
+    /// Whether every walker stands on an end node
+    pub fn finished(&self) -> bool {
+        self.walkers.iter().all(|walker| self.end.matches(walker))
+    }
+
+    /// Advances until every walker stands on an end node and returns the step, None if that does
+    /// not happen by step @limit
+    pub fn run_until_finished(&mut self, limit: u128) -> Result<Option<u128>> {
+        while !self.finished() {
+            if self.step >= limit {
+                return Ok(None);
+            }
+            self.advance()?;
+        }
+        Ok(Some(self.step))
+    }
+}
+
+impl std::fmt::Display for Simulator<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.step, self.walkers.join(", "))
+    }
+}
+
+/// The earliest step at which all walkers stand on end nodes using their cycles
+pub fn solve(board: &Board, start: &Selector, end: &Selector) -> Result<Option<u128>> {
+    solve_with(board, |node| start.matches(node), |node| end.matches(node))
+}
+
+/// Like solve() but by simulating every step, None if that takes more than @limit steps. Only
+/// usable for small networks, mostly to check solve().
+pub fn brute_force(
+    board: &Board,
+    start: &Selector,
+    end: &Selector,
+    limit: u128,
+) -> Result<Option<u128>> {
+    Simulator::new(board, start, end.clone())?.run_until_finished(limit)
+}
+
+/// Command line options to pick the start and end nodes, to simulate the walkers and to export
+/// the network
+#[derive(Debug, clap::Args)]
+pub struct SimulatorOptions {
+    /// The nodes the walkers start on, a regex or names:NAME,NAME,... Defaults to the nodes of
+    /// this part
+    #[arg(long)]
+    pub start: Option<Selector>,
+
+    /// The nodes the walkers have to reach, a regex or names:NAME,NAME,... Defaults to the nodes
+    /// of this part
+    #[arg(long)]
+    pub end: Option<Selector>,
+
+    /// Instead of solving, print the nodes of the walkers for this many steps
+    #[arg(long)]
+    pub steps: Option<u128>,
+
+    /// Solve by simulating every step instead of using cycles, giving up after this many steps
+    #[arg(long)]
+    pub brute_force: Option<u128>,
+
+    /// Instead of solving, print the network with the start and end nodes and the cycle of each
+    /// walker highlighted
+    #[arg(long, value_enum)]
+    pub export: Option<Format>,
+
+    /// Put the nodes reachable from each start node in a cluster of their own when exporting
+    #[arg(long, requires = "export")]
+    pub clusters: bool,
+}
+
+impl SimulatorOptions {
+    /// Prints the solution, the steps of the walkers, or the network for the selected nodes.
+    /// @start and @end are used when the options do not select any nodes.
+    pub fn run(&self, board: &Board, start: Selector, end: Selector) -> Result<()> {
+        let start = self.start.clone().unwrap_or(start);
+        let end = self.end.clone().unwrap_or(end);
+
+        if let Some(format) = self.export {
+            let graph = Graph::new(board, &start, &end, self.clusters)?;
+            print!("{}", graph.export(format));
+            return Ok(());
+        }
+
+        if let Some(steps) = self.steps {
+            let mut simulator = Simulator::new(board, &start, end)?;
+            println!("{simulator}");
+            for _ in 0..steps {
+                simulator.advance()?;
+                println!("{simulator}");
+            }
+            return Ok(());
+        }
+
+        let solution = match self.brute_force {
+            Some(limit) => brute_force(board, &start, &end, limit)?,
+            None => solve(board, &start, &end)?,
+        };
+        match solution {
+            Some(solution) => println!("{solution}"),
+            None if self.brute_force.is_some() => {
+                println!("The walkers do not all stand on end nodes within the limit")
+            }
+            None => println!("The walkers never all stand on end nodes at the same time"),
+        }
+
+        Ok(())
+    }
+}
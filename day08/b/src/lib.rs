@@ -0,0 +1,8 @@
+#[path = "../../a/src/export.rs"]
+pub mod export;
+#[path = "../../a/src/ghost.rs"]
+pub mod ghost;
+#[path = "../../a/src/network.rs"]
+pub mod network;
+#[path = "../../a/src/simulator.rs"]
+pub mod simulator;
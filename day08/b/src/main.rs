@@ -1,161 +1,135 @@
-use anyhow::{Context, Result};
-use clap::{arg, command, Parser};
-use itertools::iproduct;
-use num::integer::lcm;
-use std::collections::{HashMap, HashSet};
-
-#[derive(Debug)]
-enum Direction {
-    Left,
-    Right,
-}
-
-#[derive(Debug)]
-struct Board {
-    maze: HashMap<String, (String, String)>,
-    directions: Vec<Direction>,
-}
-
-fn parse_text(text: &str) -> Result<Board> {
-    let mut lines = text.lines();
-
-    let directions: Vec<Direction> = lines
-        .next()
-        .with_context(|| "Extracting directions")
-        .unwrap()
-        .trim()
-        .chars()
-        .map(|c| match c {
-            'L' => Direction::Left,
-            'R' => Direction::Right,
-            c => unreachable!("Unexpected character {c}"),
-        })
-        .collect();
-    let mut maze = HashMap::new();
-
-    for line in lines.map(|x| x.trim()).filter(|x| x.len() > 0) {
-        let (curr, next) = line
-            .split_once("=")
-            .with_context(|| "Splitting a Maze line on =")
-            .unwrap();
-        let curr = curr.trim();
-        let (left, right) = next
-            .trim()
-            .split_once(",")
-            .with_context(|| "Splitting next nodes")
-            .unwrap();
-        let left = left.trim();
-        let right = right.trim();
-
-        let left = left
-            .strip_prefix('(')
-            .with_context(|| "Stripping prefix in Left")
-            .unwrap();
-
-        let right = right
-            .strip_suffix(')')
-            .with_context(|| "Stripping suffix in Right")
-            .unwrap();
-        maze.insert(curr.to_string(), (left.to_string(), right.to_string()));
-    }
-
-    Ok(Board { directions, maze })
-}
-
-fn parse_path(path: &std::path::Path) -> Result<Board> {
-    let contents = std::fs::read_to_string(path)
-        .with_context(|| "Reading file")
-        .unwrap();
+use anyhow::Result;
+use b::network::parse_path;
+use b::simulator::SimulatorOptions;
+use clap::Parser;
 
-    parse_text(&contents)
-}
+#[test]
+fn test_sample_0() -> Result<()> {
+    let sample = "LR
 
-fn walk_to_any_end_node(board: &Board, curr: &str) -> Vec<u128> {
-    let mut steps = 0u128;
-    let mut curr = curr;
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+    let board = b::network::parse_text(sample)?;
 
-    let mut explored: HashSet<(String, usize)> = HashSet::new();
-    let mut ret = vec![];
-    let mut end_nodes_visited = HashSet::new();
+    println!("Board: {board:#?}");
 
-    loop {
-        let dir_index = (steps % board.directions.len() as u128) as usize;
-        let dir = &board.directions[dir_index];
-        steps += 1;
+    let solution = b::ghost::solve(&board)?;
 
-        let options = board
-            .maze
-            .get(curr)
-            .with_context(|| "Getting possible next nodes")
-            .unwrap();
+    assert_eq!(solution, Some(6));
 
-        let next = (curr.to_string(), dir_index);
+    Ok(())
+}
 
-        if let Some(_) = explored.get(&next) {
-            // VV: Node is unreachable
-            return ret;
+#[test]
+fn test_offset_cycles() -> Result<()> {
+    use b::ghost::{combine, Walk};
+
+    // VV: 11A enters its 2 step cycle at step 2 and 33A enters its 3 step cycle at step 2, so the
+    // ghosts first meet the end nodes at step 5 rather than lcm(2, 3)
+    let sample = "L
+
+11A = (11B, 11B)
+11B = (11C, 11C)
+11C = (11Z, 11Z)
+11Z = (11C, 11C)
+33A = (33B, 33B)
+33B = (33Z, 33Z)
+33Z = (33C, 33C)
+33C = (33D, 33D)
+33D = (33Z, 33Z)";
+    let board = b::network::parse_text(sample)?;
+    let walk = Walk::new(&board, "11A", |node| node.ends_with('Z'))?;
+    assert_eq!(
+        walk,
+        Walk {
+            prefix: vec![],
+            start: 2,
+            length: 2,
+            offsets: vec![3]
         }
+    );
+    assert_eq!(b::ghost::solve(&board)?, Some(5));
 
-        explored.insert(next);
-
-        curr = match dir {
-            Direction::Left => &options.0,
-            Direction::Right => &options.1,
-        }
-        .as_ref();
+    // VV: 22A only reaches 22Z once, before the others do
+    let board = b::network::parse_text(&format!(
+        "{sample}
+22A = (22Z, 22Z)
+22Z = (22B, 22B)
+22B = (22C, 22C)
+22C = (22B, 22B)"
+    ))?;
+    assert_eq!(
+        Walk::new(&board, "22A", |node| node.ends_with('Z'))?.prefix,
+        [1]
+    );
+    assert_eq!(b::ghost::solve(&board)?, None);
 
-        if curr.ends_with('Z') && !end_nodes_visited.contains(&curr.to_string()) {
-            end_nodes_visited.insert(curr.to_string());
+    assert_eq!(combine((1, 4), (3, 6))?, Some((9, 12)));
+    assert_eq!(combine((2, 4), (3, 6))?, None);
 
-            ret.push(steps)
-        }
-    }
+    Ok(())
 }
 
-// VV: Produced this starting from https://stackoverflow.com/a/65780800
-fn product(vectors: Vec<Vec<u128>>) -> Vec<Vec<u128>> {
-    let mut result: Vec<Vec<u128>> = vec![vec![]];
-
-    for vector in vectors {
-        result = iproduct!(result.iter(), vector.iter())
-            .map(|(v, x)| {
-                let mut v1 = v.clone();
-                v1.push(*x);
-                v1
-            })
-            .collect();
-    }
-
-    result
-}
+#[test]
+fn test_simulator() -> Result<()> {
+    use b::simulator::{brute_force, solve, Selector, Simulator};
 
-fn solve(board: &Board) -> u128 {
-    // VV: A vector of vectors. Each outer vector represents one A-Node. Each inner vector is the
-    // steps to walk from said A-Node to the reachable Z-Nodes;
-    let mut book = vec![];
+    let sample = "LR
 
-    // VV: Visit all reachable Z-Nodes from all A-Nodes and record the distance (in number of steps)
-    for curr in board.maze.keys() {
-        if curr.ends_with('A') {
-            book.push(walk_to_any_end_node(&board, curr))
-        }
+11A = (11B, XXX)
+11B = (XXX, 11Z)
+11Z = (11B, XXX)
+22A = (22B, XXX)
+22B = (22C, 22C)
+22C = (22Z, 22Z)
+22Z = (22B, 22B)
+XXX = (XXX, XXX)";
+    let board = b::network::parse_text(sample)?;
+    let ghosts: Selector = "A$".parse()?;
+    let ends: Selector = "Z$".parse()?;
+
+    let mut simulator = Simulator::new(&board, &ghosts, ends.clone())?;
+    simulator.advance()?;
+    simulator.advance()?;
+    assert_eq!(simulator.to_string(), "2: 11Z, 22C");
+    assert!(!simulator.finished());
+    assert_eq!(simulator.run_until_finished(100)?, Some(6));
+
+    // VV: The fast solver agrees with brute force for any choice of start and end nodes
+    for (start, end) in [
+        ("A$", "Z$"),
+        ("names:11A", "names:11Z"),
+        ("names:22A,11B", "Z$"),
+        ("^22", "names:22C,22Z"),
+        ("A$", "names:11Z"),
+        ("^11", "[BZ]$"),
+    ] {
+        let (start, end): (Selector, Selector) = (start.parse()?, end.parse()?);
+        assert_eq!(
+            solve(&board, &start, &end)?,
+            brute_force(&board, &start, &end, 1000)?
+        );
     }
+    assert_eq!(brute_force(&board, &ghosts, &ends, 5)?, None);
 
-    // VV: The answer is min(lcm([a[i][z for all reachable z from a[i]] for all i])
-    product(book)
-        .iter()
-        .map(|all_steps| {
-            all_steps
-                .iter()
-                .fold(1u128, |curr_lcm, a_z_steps| lcm(curr_lcm, *a_z_steps))
-        })
-        .min()
-        .with_context(|| "Computing least-common-multiple of steps from A to Z nodes")
-        .unwrap()
+    assert!("names:".parse::<Selector>().is_err());
+    assert!("(".parse::<Selector>().is_err());
+    assert!(Simulator::new(&board, &"names:QQQ".parse()?, ends).is_err());
+
+    Ok(())
 }
 
 #[test]
-fn test_sample_0() -> Result<()> {
+fn test_export() -> Result<()> {
+    use b::export::{Format, Graph};
+    use std::collections::BTreeSet;
+
     let sample = "LR
 
 11A = (11B, XXX)
@@ -166,13 +140,34 @@ fn test_sample_0() -> Result<()> {
 22C = (22Z, 22Z)
 22Z = (22B, 22B)
 XXX = (XXX, XXX)";
-    let board = parse_text(sample)?;
-
-    println!("Board: {board:#?}");
-
-    let solution = solve(&board);
-
-    assert_eq!(solution, 6);
+    let board = b::network::parse_text(sample)?;
+    let graph = Graph::new(&board, &"A$".parse()?, &"Z$".parse()?, true)?;
+
+    assert_eq!(graph.starts, ["11A", "22A"]);
+    assert_eq!(graph.cycles[0], BTreeSet::from(["11B", "11Z"]));
+    assert_eq!(graph.cycles[1], BTreeSet::from(["22B", "22C", "22Z"]));
+    // VV: XXX is reachable from both start nodes but only goes in the first cluster
+    let clusters = graph.clusters.clone().unwrap_or_default();
+    assert_eq!(clusters[0], BTreeSet::from(["11A", "11B", "11Z", "XXX"]));
+    assert_eq!(clusters[1], BTreeSet::from(["22A", "22B", "22C", "22Z"]));
+
+    let dot = graph.export(Format::Dot);
+    assert!(dot.starts_with("digraph network {\n    subgraph cluster_0 {\n"));
+    assert!(dot.contains("    \"11B\" -> \"11Z\" [label=R];\n"));
+    assert!(dot.contains("    \"22B\" -> \"22C\" [label=LR];\n"));
+    assert!(dot.contains(
+        "    \"11Z\" [style=filled, fillcolor=lightcoral, color=blue, penwidth=3, tooltip=\"cycle of 11A\"];\n"
+    ));
+
+    let graphml = graph.export(Format::Graphml);
+    assert_eq!(graphml.matches("<node id=").count(), 8 + 2);
+    assert_eq!(graphml.matches("<edge ").count(), 12);
+    assert!(graphml.contains("<graph id=\"cluster_1:\" edgedefault=\"directed\">"));
+    assert!(graphml.ends_with("  </graph>\n</graphml>\n"));
+
+    let plain = Graph::new(&board, &"names:22A".parse()?, &"Z$".parse()?, false)?;
+    assert!(!plain.export(Format::Dot).contains("subgraph"));
+    assert!(!plain.export(Format::Dot).contains("\n    \"11B\" ["));
 
     Ok(())
 }
@@ -182,6 +177,9 @@ XXX = (XXX, XXX)";
 struct Args {
     #[arg(short, long, default_value = "input/mine")]
     input: std::path::PathBuf,
+
+    #[command(flatten)]
+    simulator: SimulatorOptions,
 }
 
 fn main() -> Result<()> {
@@ -190,9 +188,5 @@ fn main() -> Result<()> {
 
     let board = parse_path(&path)?;
 
-    let solution = solve(&board);
-
-    println!("{solution}");
-
-    Ok(())
+    args.simulator.run(&board, "A$".parse()?, "Z$".parse()?)
 }